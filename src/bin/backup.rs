@@ -1,35 +1,355 @@
-use cargo_backup::{get_packages, Package};
-use clap::{builder::ValueParser, command, Arg, Command};
+use cargo_backup::{
+    apply_previous,
+    check::{check, Severity},
+    diff::diff,
+    get_packages, history, io,
+    merge::{merge, Strategy},
+    platform::{self, Host},
+    profile,
+    report::{self, Event},
+    schema, sign, Package,
+};
+use clap::{builder::ValueParser, command, Arg, ArgAction, ArgMatches, Command};
+use common::{get_filter, get_profile, init_output, or_exit, output_args};
 use std::{fs, path::PathBuf};
 
+mod common;
+
 fn main() {
     let args = Command::new("cargo")
         .bin_name("cargo")
         .about("Creates a backup of your installed cargo packages")
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
+        .args(output_args())
         .subcommand(
-            command!("backup").arg(
-                Arg::new("out")
-                    .long("out")
-                    .short('o')
-                    .value_parser(ValueParser::path_buf())
-                    .help("The output file to write to")
-                    .default_value("./backup.json"),
-            ),
+            command!("backup")
+                .args_conflicts_with_subcommands(true)
+                .subcommand(
+                    Command::new("schema").about("Prints the JSON Schema of the backup format"),
+                )
+                .subcommand(
+                    Command::new("merge")
+                        .about("Merges multiple backups into one")
+                        .arg(
+                            Arg::new("files")
+                                .help("The backups to merge, in order")
+                                .value_parser(ValueParser::string())
+                                .num_args(2..)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("out")
+                                .long("out")
+                                .short('o')
+                                .value_parser(ValueParser::string())
+                                .help("The output file to write to, or - to write to stdout")
+                                .default_value(io::STDIO),
+                        )
+                        .arg(
+                            Arg::new("strategy")
+                                .long("strategy")
+                                .short('s')
+                                .value_parser(["union", "intersection", "override"])
+                                .default_value("union")
+                                .help("union: all packages, highest version and combined features win; intersection: only packages in every backup; override: later backups replace earlier ones"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("diff")
+                        .about("Compares two backups, or a backup with the installed packages")
+                        .arg(
+                            Arg::new("from")
+                                .help("The old backup")
+                                .value_parser(ValueParser::string())
+                                .default_value("./backup.json"),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .help("The new backup (defaults to the installed packages)")
+                                .value_parser(ValueParser::string()),
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the diff as JSON")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("exit-code")
+                                .long("exit-code")
+                                .help("Exit with 1 if there are differences")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(Command::new("profiles").about("Lists the profiles with a local backup"))
+                .subcommand(
+                    Command::new("history")
+                        .about("Lists the previous restores, newest first")
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("10")
+                                .help("The number of restores to list"),
+                        )
+                        .arg(
+                            Arg::new("package")
+                                .long("package")
+                                .value_parser(ValueParser::string())
+                                .help("Only list restores that changed the package"),
+                        )
+                        .arg(
+                            Arg::new("failed")
+                                .long("failed")
+                                .help("Only list restores with failed packages")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Checks a backup for problems without applying it")
+                        .arg(
+                            Arg::new("file")
+                                .help("The backup file or URL to check, or - to read from stdin")
+                                .value_parser(ValueParser::string())
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .short('o')
+                        .value_parser(ValueParser::string())
+                        .help("The output file to write to, or - to write to stdout (defaults to ./backup.json, or the profile backup with --profile)"),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .value_parser(ValueParser::string())
+                        .help("The profile to back up to, stored in the config directory"),
+                )
+                .arg(
+                    Arg::new("sign")
+                        .long("sign")
+                        .short('s')
+                        .help("Sign the backup and write the signature next to it")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .short('k')
+                        .value_parser(ValueParser::path_buf())
+                        .help("The ssh private key to sign with (defaults to the configured key)"),
+                )
+                .arg(
+                    Arg::new("signature-out")
+                        .long("signature-out")
+                        .value_parser(ValueParser::string())
+                        .required_if_eq_all([("out", io::STDIO), ("sign", "true")])
+                        .help("The file to write the signature to (defaults to <out>.sig)"),
+                )
+                .arg(
+                    Arg::new("groups-from")
+                        .long("groups-from")
+                        .value_parser(ValueParser::string())
+                        .help(
+                            "The backup to copy groups and platform conditions from (defaults to the output file)",
+                        ),
+                )
+                .arg(
+                    Arg::new("only")
+                        .long("only")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .help("Only include packages matching the given names or glob patterns"),
+                )
+                .arg(
+                    Arg::new("exclude")
+                        .long("exclude")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .help("Exclude packages matching the given names or glob patterns"),
+                ),
         )
         .get_matches();
 
+    init_output(&args);
+
     match args.subcommand() {
-        Some(("backup", args)) => {
-            let packages: Vec<Package> = get_packages();
+        Some(("backup", args)) => match args.subcommand() {
+            Some(("schema", _)) => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&schema::schema()).expect("Failed to serialize")
+                );
+            }
+            Some(("check", args)) => {
+                let file = args.get_one::<String>("file").unwrap();
+                let content = or_exit(file, io::read_backup(file));
 
-            let out = args.get_one::<PathBuf>("out").unwrap();
+                let problems = check(&content, &get_packages());
 
-            let backup = serde_json::to_string(&packages).expect("Failed to serialize");
+                for problem in &problems {
+                    report::event(&Event::Problem { problem });
+                }
 
-            fs::write(out, backup).expect("Failed to write backup");
-        }
+                let errors = problems
+                    .iter()
+                    .filter(|problem| problem.severity == Severity::Error)
+                    .count();
+
+                report::info(format!(
+                    "{}: {} error(s), {} warning(s)",
+                    file,
+                    errors,
+                    problems.len() - errors
+                ));
+
+                if errors > 0 {
+                    std::process::exit(1);
+                }
+            }
+            Some(("merge", args)) => {
+                let strategy: Strategy =
+                    args.get_one::<String>("strategy").unwrap().parse().unwrap();
+
+                let backups: Vec<Vec<Package>> = args
+                    .get_many::<String>("files")
+                    .unwrap()
+                    .map(|file| {
+                        let content = or_exit(file, io::read_backup(file));
+                        schema::parse(&content).unwrap_or_else(|e| {
+                            report::error(format!("{}: {}", file, e));
+                            std::process::exit(1);
+                        })
+                    })
+                    .collect();
+
+                let (packages, conflicts) = merge(&backups, strategy);
+
+                for conflict in conflicts {
+                    report::warn(format!("Conflict: {}", conflict));
+                }
+
+                let backup = serde_json::to_string(&packages).expect("Failed to serialize");
+                io::write_backup(args.get_one::<String>("out").unwrap(), &backup)
+                    .expect("Failed to write backup");
+            }
+            Some(("diff", args)) => {
+                let read = |file: &String| {
+                    let content = or_exit(file, io::read_backup(file));
+                    schema::parse(&content).unwrap_or_else(|e| {
+                        report::error(format!("{}: {}", file, e));
+                        std::process::exit(1);
+                    })
+                };
+
+                let from = read(args.get_one::<String>("from").unwrap());
+
+                let (from, to) = match args.get_one::<String>("to") {
+                    Some(to) => (from, read(to)),
+                    // Compare the packages meant for this platform with the installed packages
+                    None => (platform::resolve(from, &Host::current()).0, get_packages()),
+                };
+
+                let result = diff(&from, &to);
+
+                if args.get_flag("json") {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&result).expect("Failed to serialize")
+                    );
+                } else {
+                    report::event(&Event::Diff { diff: &result });
+                }
+
+                if args.get_flag("exit-code") && !result.is_empty() {
+                    std::process::exit(1);
+                }
+            }
+            Some(("profiles", _)) => {
+                for profile in profile::list() {
+                    report::event(&Event::Profile {
+                        name: &profile.to_string(),
+                        path: &profile.path(),
+                    });
+                }
+            }
+            Some(("history", args)) => print_history(args),
+            _ => create_backup(args),
+        },
         _ => unreachable!(),
     }
 }
+
+fn create_backup(args: &ArgMatches) {
+    let out = match args.get_one::<String>("out") {
+        Some(out) => out.to_string(),
+        None if args.contains_id("profile") => {
+            let path = get_profile(args).path();
+            fs::create_dir_all(path.parent().unwrap()).expect("Failed to create profile directory");
+            path.to_string_lossy().to_string()
+        }
+        None => String::from("./backup.json"),
+    };
+    let out = &out;
+
+    let mut packages = get_packages();
+
+    // Keep the groups and platform conditions of the previous backup
+    let previous = args
+        .get_one::<String>("groups-from")
+        .or((out != io::STDIO).then_some(out));
+    if let Some(previous) = previous.and_then(|path| io::read_backup(path).ok()) {
+        if let Ok(previous) = schema::parse(&previous) {
+            apply_previous(&mut packages, &previous);
+        }
+    }
+
+    let (packages, skipped): (Vec<Package>, _) = get_filter(args).partition(packages);
+    for package in skipped {
+        report::info(format!("Skipping {} (filtered)", package.name));
+    }
+
+    let backup = serde_json::to_string(&packages).expect("Failed to serialize");
+
+    io::write_backup(out, &backup).expect("Failed to write backup");
+
+    if args.get_flag("sign") || args.contains_id("key") || args.contains_id("signature-out") {
+        let key = args
+            .get_one::<PathBuf>("key")
+            .cloned()
+            .or(sign::Config::load().key)
+            .expect("No signing key configured, use --key");
+
+        let signature = sign::sign(backup.as_bytes(), &key).expect("Failed to sign backup");
+
+        let signature_out = match args.get_one::<String>("signature-out") {
+            Some(path) => io::expand_path(path),
+            None => io::expand_path(out).map(|out| sign::signature_path(&out)),
+        }
+        .expect("Failed to expand path");
+
+        fs::write(signature_out, signature).expect("Failed to write signature");
+    }
+}
+
+fn print_history(args: &ArgMatches) {
+    let package = args.get_one::<String>("package").map(String::as_str);
+
+    let entries = history::read().into_iter().rev().filter(|entry| {
+        (!args.get_flag("failed") || entry.failures().next().is_some())
+            && package.is_none_or(|name| entry.outcomes.iter().any(|o| o.package == name))
+    });
+
+    for mut entry in entries.take(*args.get_one::<usize>("limit").unwrap()) {
+        // Only the outcomes of the package, in every output format
+        if let Some(name) = package {
+            entry.outcomes.retain(|outcome| outcome.package == name);
+        }
+        report::event(&Event::History { entry: &entry });
+    }
+}
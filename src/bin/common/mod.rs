@@ -0,0 +1,163 @@
+//! Arguments and helpers shared by the cargo-backup, cargo-restore and cargo-sync binaries.
+#![allow(dead_code)]
+
+use cargo_backup::{
+    color,
+    filter::Filter,
+    install, io,
+    plan::{Plan, PlanOptions},
+    profile::Profile,
+    report::{self, Verbosity},
+    Confirmation, RestoreError, RestoreOptions,
+};
+use clap::{Arg, ArgAction, ArgMatches};
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The global output arguments read by [`init_output`].
+pub fn output_args() -> [Arg; 4] {
+    [
+        Arg::new("output")
+            .long("output")
+            .global(true)
+            .value_parser(["human", "plain", "json"])
+            .default_value("human")
+            .help("human: colored trees; plain: one line per item; json: one JSON object per line"),
+        Arg::new("color")
+            .long("color")
+            .global(true)
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto")
+            .help("When to color the output, auto honors NO_COLOR and CLICOLOR_FORCE"),
+        Arg::new("quiet")
+            .long("quiet")
+            .short('q')
+            .global(true)
+            .conflicts_with("verbose")
+            .help("Only print errors, warnings and results")
+            .action(ArgAction::SetTrue),
+        Arg::new("verbose")
+            .long("verbose")
+            .short('v')
+            .global(true)
+            .help("Also print debug messages, e.g. the cargo commands that run")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
+/// Sets up the colors and the reporter from the output arguments.
+pub fn init_output(args: &ArgMatches) {
+    color::init(args.get_one::<String>("color").unwrap().parse().unwrap());
+
+    let verbosity = if args.get_flag("quiet") {
+        Verbosity::Quiet
+    } else if args.get_flag("verbose") {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    };
+
+    report::init(
+        args.get_one::<String>("output").unwrap().parse().unwrap(),
+        verbosity,
+    );
+}
+
+/// Returns the values of a multi value argument, or none if the command does not have it.
+fn values(args: &ArgMatches, id: &str) -> Vec<String> {
+    args.try_get_many::<String>(id)
+        .ok()
+        .flatten()
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Builds the filter from `--only`, `--exclude` and `--group`.
+pub fn get_filter(args: &ArgMatches) -> Filter {
+    Filter::new(values(args, "only"), values(args, "exclude")).with_groups(values(args, "group"))
+}
+
+/// Builds the restore options from the arguments of `cargo restore` and `cargo sync pull`.
+/// The sources are left empty.
+pub fn get_options(args: &ArgMatches) -> RestoreOptions {
+    RestoreOptions {
+        plan: PlanOptions {
+            skip_install: args.get_flag("skip-install"),
+            skip_update: args.get_flag("skip-update"),
+            skip_remove: args.get_flag("skip-remove"),
+            filter: get_filter(args),
+            install: install::Config::load(),
+            cargo: std::env::var("CARGO").ok(),
+        },
+        dry_run: args.get_flag("dry-run"),
+        confirm: if args.get_flag("yes") {
+            Confirmation::Never
+        } else if args.get_flag("select") {
+            Confirmation::Select
+        } else if args.get_flag("confirm-removals-only") {
+            Confirmation::RemovalsOnly
+        } else {
+            Confirmation::Always
+        },
+        sources: vec![],
+        wait: args.get_flag("wait"),
+    }
+}
+
+/// Returns the profile of `--profile`, exiting if the name is invalid.
+pub fn get_profile(args: &ArgMatches) -> Profile {
+    Profile::new(args.get_one::<String>("profile").map(String::as_str)).unwrap_or_else(|e| {
+        report::error(e);
+        std::process::exit(1);
+    })
+}
+
+/// Keeps stdout free for the JSON plan if --plan-out writes it there,
+/// so the plan is not printed for humans as well.
+pub fn reserve_stdout_for_plan(args: &ArgMatches) {
+    if args.get_one::<String>("plan-out").map(String::as_str) == Some(io::STDIO) {
+        report::hide_plans();
+    }
+}
+
+/// Writes the plan to the file of --plan-out, if given.
+pub fn write_plan(args: &ArgMatches, plan: &Plan) {
+    if let Some(out) = args.get_one::<String>("plan-out") {
+        let json = serde_json::to_string_pretty(plan).expect("Failed to serialize");
+        io::write_backup(out, &json).expect("Failed to write plan");
+    }
+}
+
+/// Stops the restore on the first Ctrl-C and quits on the second.
+/// cargo runs in the same process group, so the first Ctrl-C cancels the current package as well.
+pub fn handle_interrupts() {
+    ctrlc::set_handler(|| {
+        static PRESSED: AtomicBool = AtomicBool::new(false);
+
+        if PRESSED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        report::warn("Cancelling the current package and stopping, press Ctrl-C again to quit");
+        cargo_backup::interrupt();
+    })
+    .expect("Failed to set the Ctrl-C handler");
+}
+
+/// Returns the value, or reports the error with the file or url it is about and exits.
+pub fn or_exit<T, E: fmt::Display>(source: impl fmt::Display, result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        report::error(format!("{}: {}", source, e));
+        std::process::exit(1);
+    })
+}
+
+/// Reports the error and exits, with 130 if the restore was interrupted.
+pub fn exit_with(e: RestoreError) -> ! {
+    report::error(&e);
+    std::process::exit(match e {
+        RestoreError::Interrupted => 130,
+        _ => 1,
+    });
+}
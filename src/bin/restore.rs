@@ -1,12 +1,24 @@
-use cargo_backup::{install_packages, Package};
+use cargo_backup::{
+    history, install_packages, io,
+    merge::{merge, Strategy},
+    report, resume_restore,
+    runner::CargoRunner,
+    schema, sign, Package, RestoreOptions,
+};
 use clap::{builder::ValueParser, command, Arg, ArgAction, Command};
-use std::{fs, path::PathBuf};
+use common::{
+    exit_with, get_options, get_profile, handle_interrupts, init_output, or_exit, output_args,
+    reserve_stdout_for_plan, write_plan,
+};
+
+mod common;
 
 fn main() {
     let args = Command::new("cargo")
         .about("Restores a backup created by cargo-backup")
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
+        .args(output_args())
         .bin_name("cargo")
         .subcommand(
             command!("restore")
@@ -14,9 +26,23 @@ fn main() {
                     Arg::new("input")
                         .long("backup")
                         .short('b')
-                        .value_parser(ValueParser::path_buf())
-                        .required(true)
-                        .help("The input file to restore from"),
+                        .value_parser(ValueParser::string())
+                        .action(ArgAction::Append)
+                        .required_unless_present_any(["profile", "resume"])
+                        .help("The file or https/file URL to restore from (http requires --sha256), or - to read from stdin. Multiple backups are layered in order"),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .value_parser(ValueParser::string())
+                        .help("Restore the backup of the profile created with cargo backup --profile, layered after --backup"),
+                )
+                .arg(
+                    Arg::new("sha256")
+                        .long("sha256")
+                        .value_parser(ValueParser::string())
+                        .action(ArgAction::Append)
+                        .help("The expected sha256 checksum of the backup, once per backup"),
                 )
                 .arg(
                     Arg::new("skip-install")
@@ -38,28 +64,230 @@ fn main() {
                         .long("skip-remove")
                         .help("Skip removal of Packages not found in the backup")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-verify")
+                        .long("no-verify")
+                        .help("Restore even if the backup signature cannot be verified")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("signature")
+                        .long("signature")
+                        .value_parser(ValueParser::string())
+                        .action(ArgAction::Append)
+                        .help("The signature of the backup, once per backup (defaults to <backup>.sig)"),
+                )
+                .arg(
+                    Arg::new("only")
+                        .long("only")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .help("Only include packages matching the given names or glob patterns"),
+                )
+                .arg(
+                    Arg::new("exclude")
+                        .long("exclude")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .help("Exclude packages matching the given names or glob patterns"),
+                )
+                .arg(
+                    Arg::new("group")
+                        .long("group")
+                        .short('g')
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .help("Only restore packages in the given groups"),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .visible_alias("no-confirm")
+                        .help("Restore without asking for confirmation")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("confirm-removals-only")
+                        .long("confirm-removals-only")
+                        .conflicts_with("yes")
+                        .help("Install and update without confirmation, but ask before removing packages")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("select")
+                        .long("select")
+                        .conflicts_with_all(["yes", "confirm-removals-only"])
+                        .help("Pick the packages to install, update and remove from a list")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("resume")
+                        .long("resume")
+                        .conflicts_with_all([
+                            "input",
+                            "profile",
+                            "sha256",
+                            "signature",
+                            "no-verify",
+                            "only",
+                            "exclude",
+                            "group",
+                            "skip-install",
+                            "skip-update",
+                            "skip-remove",
+                        ])
+                        .help("Continue the last interrupted restore with the steps that did not run yet or failed")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("wait")
+                        .long("wait")
+                        .help("Wait for another restore to finish instead of failing")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .short('n')
+                        .help("Print the plan without installing or removing anything")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("plan-out")
+                        .long("plan-out")
+                        .value_parser(ValueParser::string())
+                        .requires("dry-run")
+                        .help("Write the plan with the cargo commands as JSON to the file, or - for stdout"),
                 ),
         )
         .get_matches();
 
+    init_output(&args);
+
     match args.subcommand() {
+        Some(("restore", args)) if args.get_flag("resume") => {
+            reserve_stdout_for_plan(args);
+            handle_interrupts();
+            let plan = resume_restore(
+                &get_options(args),
+                &mut CargoRunner::with_log_dir(history::new_log_dir()),
+            )
+            .unwrap_or_else(|e| exit_with(e));
+            write_plan(args, &plan);
+        }
         Some(("restore", args)) => {
-            let input = args.get_one::<PathBuf>("input").unwrap();
-            // let input =
-            //     shellexpand::full(args.value_of("input").unwrap()).expect("Failed to expand path");
+            let mut inputs: Vec<io::Source> = args
+                .get_many::<String>("input")
+                .into_iter()
+                .flatten()
+                .map(|input| or_exit(input, io::Source::parse(input)))
+                .collect();
+
+            if args.contains_id("profile") {
+                inputs.push(io::Source::File(get_profile(args).path()));
+            }
+
+            let checksums: Vec<&String> = args.get_many("sha256").into_iter().flatten().collect();
+            if !checksums.is_empty() && checksums.len() != inputs.len() {
+                report::error("--sha256 must be given once per backup");
+                std::process::exit(1);
+            }
+
+            let signatures: Vec<&String> =
+                args.get_many("signature").into_iter().flatten().collect();
 
-            let backup = fs::read_to_string(input).expect("Failed to read backup");
+            let backups: Vec<Vec<Package>> = inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    load_backup(
+                        input,
+                        checksums.get(i).copied(),
+                        signatures.get(i).copied(),
+                        args.get_flag("no-verify"),
+                    )
+                })
+                .collect();
 
-            let packages: Vec<Package> =
-                serde_json::from_str(&backup).expect("Failed to parse JSON");
+            let (packages, conflicts) = merge(&backups, Strategy::Override);
 
-            install_packages(
+            for conflict in conflicts {
+                report::warn(format!("Conflict: {}", conflict));
+            }
+
+            let options = RestoreOptions {
+                sources: inputs.iter().map(ToString::to_string).collect(),
+                ..get_options(args)
+            };
+
+            reserve_stdout_for_plan(args);
+            handle_interrupts();
+            let plan = install_packages(
                 &packages,
-                args.get_flag("skip-install"),
-                args.get_flag("skip-update"),
-                args.get_flag("skip-remove"),
+                &options,
+                &mut CargoRunner::with_log_dir(history::new_log_dir()),
             )
+            .unwrap_or_else(|e| exit_with(e));
+            write_plan(args, &plan);
         }
         _ => unreachable!(),
     }
 }
+
+/// Reads, verifies and parses a backup, exiting on errors.
+fn load_backup(
+    input: &io::Source,
+    checksum: Option<&String>,
+    signature: Option<&String>,
+    no_verify: bool,
+) -> Vec<Package> {
+    if input.is_insecure() && checksum.is_none() {
+        report::error(format!(
+            "{}: refusing to restore over plain http without --sha256, use https",
+            input
+        ));
+        std::process::exit(1);
+    }
+
+    let backup = or_exit(input, input.read());
+
+    if let Some(checksum) = checksum {
+        if let Err(e) = io::verify_checksum(backup.as_bytes(), checksum) {
+            report::error(format!("{}: {}", input, e));
+            std::process::exit(1);
+        }
+    }
+
+    let explicit = signature.is_some();
+    let signature = match signature {
+        Some(path) => Some(or_exit(path, io::read_backup(path))),
+        None => input.read_signature(),
+    };
+
+    // A signature passed with --signature must not be skipped silently
+    let result = match sign::check(backup.as_bytes(), signature.as_deref()) {
+        Ok(None) if explicit => Err(sign::SignError::NoTrustedSigners),
+        result => result,
+    };
+
+    match result {
+        Ok(Some(principal)) => report::info(format!("{} signed by {}", input, principal)),
+        Ok(None) => {}
+        Err(e) if no_verify => report::warn(format!("{}: {}", input, e)),
+        Err(e) => {
+            report::error(format!("{}: {}", input, e));
+            report::info("Use --no-verify to restore anyway");
+            std::process::exit(1);
+        }
+    }
+
+    match schema::parse(&backup) {
+        Ok(packages) => packages,
+        Err(e) => {
+            report::error(format!("{}: {}", input, e));
+            std::process::exit(1);
+        }
+    }
+}
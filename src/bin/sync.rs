@@ -1,14 +1,21 @@
-use cargo_backup::get_packages;
 use cargo_backup::remote::RemoteProvider;
+use cargo_backup::{get_packages, history, report, runner::CargoRunner, sign, RestoreOptions};
 use cargo_backup::{install_packages, remote::github::Github};
 use clap::builder::ValueParser;
 use clap::{command, Arg, ArgAction, Command};
+use common::{
+    exit_with, get_filter, get_options, get_profile, handle_interrupts, init_output, output_args,
+    reserve_stdout_for_plan, write_plan,
+};
+
+mod common;
 
 fn main() {
     let args = Command::new("cargo")
         .about("Restores a backup created by cargo-backup")
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
+        .args(output_args())
         .bin_name("cargo")
         .subcommand(
             command!("sync")
@@ -30,9 +37,37 @@ fn main() {
                             .action(ArgAction::SetTrue),
                     ),
                 )
-                .subcommand(command!("push"))
+                .subcommand(
+                    command!("push")
+                        .arg(
+                            Arg::new("profile")
+                                .long("profile")
+                                .value_parser(ValueParser::string())
+                                .help("The profile to push, stored under its own file name in the gist"),
+                        )
+                        .arg(
+                            Arg::new("only")
+                                .long("only")
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .help("Only include packages matching the given names or glob patterns"),
+                        )
+                        .arg(
+                            Arg::new("exclude")
+                                .long("exclude")
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .help("Exclude packages matching the given names or glob patterns"),
+                        ),
+                )
                 .subcommand(
                     command!("pull")
+                        .arg(
+                            Arg::new("profile")
+                                .long("profile")
+                                .value_parser(ValueParser::string())
+                                .help("The profile to pull"),
+                        )
                         .arg(
                             Arg::new("skip-install")
                                 .short('i')
@@ -53,6 +88,76 @@ fn main() {
                                 .long("skip-remove")
                                 .help("Skip removal of Packages not found in the backup")
                                 .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("no-verify")
+                                .long("no-verify")
+                                .help("Restore even if the backup signature cannot be verified")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("only")
+                                .long("only")
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .help("Only include packages matching the given names or glob patterns"),
+                        )
+                        .arg(
+                            Arg::new("exclude")
+                                .long("exclude")
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .help("Exclude packages matching the given names or glob patterns"),
+                        )
+                        .arg(
+                            Arg::new("group")
+                                .long("group")
+                                .short('g')
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .help("Only restore packages in the given groups"),
+                        )
+                        .arg(
+                            Arg::new("yes")
+                                .long("yes")
+                                .short('y')
+                                .visible_alias("no-confirm")
+                                .help("Restore without asking for confirmation")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("confirm-removals-only")
+                                .long("confirm-removals-only")
+                                .conflicts_with("yes")
+                                .help("Install and update without confirmation, but ask before removing packages")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("select")
+                                .long("select")
+                                .conflicts_with_all(["yes", "confirm-removals-only"])
+                                .help("Pick the packages to install, update and remove from a list")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("wait")
+                                .long("wait")
+                                .help("Wait for another restore to finish instead of failing")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .short('n')
+                                .help("Print the plan without installing or removing anything")
+                                .action(ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("plan-out")
+                                .long("plan-out")
+                                .value_parser(ValueParser::string())
+                                .requires("dry-run")
+                                .help("Write the plan with the cargo commands as JSON to the file, or - for stdout"),
                         ),
                 )
                 .subcommand(
@@ -66,23 +171,45 @@ fn main() {
         )
         .get_matches();
 
+    init_output(&args);
+
     match args.subcommand() {
         Some(("sync", args)) => {
             let provider = Github::new();
 
             match args.subcommand() {
                 Some(("pull", args)) => {
-                    let packages = provider.pull().unwrap();
-                    install_packages(
+                    let profile = get_profile(args);
+                    let packages = provider
+                        .pull(&profile, !args.get_flag("no-verify"))
+                        .unwrap_or_else(|e| {
+                            report::error(format!("github:{}: {}", profile.file_name(), e));
+                            if e.is::<sign::SignError>() {
+                                report::info("Use --no-verify to restore anyway");
+                            }
+                            std::process::exit(1);
+                        });
+                    let options = RestoreOptions {
+                        sources: vec![format!("github:{}", profile.file_name())],
+                        ..get_options(args)
+                    };
+
+                    reserve_stdout_for_plan(args);
+                    handle_interrupts();
+                    let plan = install_packages(
                         &packages,
-                        args.get_flag("skip-install"),
-                        args.get_flag("skip-update"),
-                        args.get_flag("skip-remove"),
+                        &options,
+                        &mut CargoRunner::with_log_dir(history::new_log_dir()),
                     )
+                    .unwrap_or_else(|e| exit_with(e));
+                    write_plan(args, &plan);
                 }
-                Some(("push", _)) => {
-                    let packages = get_packages();
-                    provider.push(&packages).unwrap();
+                Some(("push", args)) => {
+                    let (packages, skipped) = get_filter(args).partition(get_packages());
+                    for package in skipped {
+                        report::info(format!("Skipping {} (filtered)", package.name));
+                    }
+                    provider.push(&packages, &get_profile(args)).unwrap();
                 }
                 Some(("login", args)) => {
                     let force = args.get_flag("force");
@@ -0,0 +1,262 @@
+use crate::{
+    order,
+    platform::{self, Host},
+    schema, Package,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// Packages that are never removed without a warning, because the restore depends on them.
+pub const PROTECTED_PACKAGES: &[&str] = &["cargo-backup"];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a backup.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    pub severity: Severity,
+    /// The location of the offending value, e.g. `$[2].version`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+impl Problem {
+    fn error(path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            path,
+            message,
+        }
+    }
+
+    fn warning(path: String, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            path,
+            message,
+        }
+    }
+}
+
+/// Checks a backup for problems without applying it.
+///
+/// `installed` is used to detect protected packages that would be removed by a restore.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{check::check, get_packages};
+///
+/// for problem in check("[]", &get_packages()) {
+///     println!("{}", problem);
+/// }
+/// ```
+pub fn check(content: &str, installed: &[Package]) -> Vec<Problem> {
+    let document: Value = match serde_json::from_str(content) {
+        Ok(document) => document,
+        Err(e) => {
+            return vec![Problem::error(
+                format!("line {} column {}", e.line(), e.column()),
+                e.to_string(),
+            )]
+        }
+    };
+
+    let mut problems: Vec<Problem> = schema::validate(&document)
+        .into_iter()
+        .map(|violation| Problem::error(violation.path, violation.message))
+        .collect();
+
+    let Some(entries) = document.as_array() else {
+        return problems;
+    };
+
+    let known_fields = schema::schema();
+    let known_fields = known_fields["$defs"]["package"]["properties"]
+        .as_object()
+        .unwrap();
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let host = Host::current();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let path = format!("$[{}]", i);
+        let Some(entry) = entry.as_object() else {
+            continue;
+        };
+
+        for key in entry.keys() {
+            if !known_fields.contains_key(key) {
+                problems.push(Problem::warning(
+                    format!("{}.{}", path, key),
+                    "unknown field, it will be ignored".to_string(),
+                ));
+            }
+        }
+
+        if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            if let Some(first) = seen.get(name).copied() {
+                problems.push(Problem::error(
+                    format!("{}.name", path),
+                    format!(
+                        "duplicate package {:?}, first defined at $[{}]",
+                        name, first
+                    ),
+                ));
+            } else {
+                seen.insert(name, i);
+            }
+        }
+
+        let mut conditions = vec![];
+        if let Some(only_on) = entry.get("only_on").and_then(Value::as_str) {
+            conditions.push((format!("{}.only_on", path), only_on));
+        }
+        for (j, o) in entry
+            .get("overrides")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            if let Some(when) = o.get("when").and_then(Value::as_str) {
+                conditions.push((format!("{}.overrides[{}].when", path, j), when));
+            }
+        }
+
+        for (path, condition) in conditions {
+            if let Err(e) = platform::matches(condition, &host) {
+                problems.push(Problem::error(path, e.reason));
+            }
+        }
+
+        let all_features = entry.get("all_features").and_then(Value::as_bool) == Some(true);
+        let no_default_features =
+            entry.get("no_default_features").and_then(Value::as_bool) == Some(true);
+        let has_features = entry
+            .get("features")
+            .and_then(Value::as_array)
+            .is_some_and(|features| !features.is_empty());
+
+        if all_features && has_features {
+            problems.push(Problem::warning(
+                format!("{}.features", path),
+                "features are ignored because all_features is set".to_string(),
+            ));
+        }
+
+        if all_features && no_default_features {
+            problems.push(Problem::warning(
+                format!("{}.no_default_features", path),
+                "no_default_features has no effect because all_features is set".to_string(),
+            ));
+        }
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        let after = entry.get("after").and_then(Value::as_array);
+        for (j, name) in after.into_iter().flatten().enumerate() {
+            if let Some(name) = name.as_str().filter(|name| !seen.contains_key(name)) {
+                problems.push(Problem::warning(
+                    format!("$[{}].after[{}]", i, j),
+                    format!("{:?} is not in the backup, it will be ignored", name),
+                ));
+            }
+        }
+    }
+
+    if let Ok(packages) = serde_json::from_value::<Vec<Package>>(document.clone()) {
+        if let Err(e) = order::sort(&packages.iter().collect::<Vec<_>>()) {
+            problems.push(Problem::error(
+                format!("$[{}].after", seen[e.cycle[0].as_str()]),
+                e.to_string(),
+            ));
+        }
+    }
+
+    for package in installed {
+        if PROTECTED_PACKAGES.contains(&package.name.as_str())
+            && !seen.contains_key(package.name.as_str())
+        {
+            problems.push(Problem::warning(
+                "$".to_string(),
+                format!(
+                    "restoring would remove the protected package {:?}, use --skip-remove",
+                    package.name
+                ),
+            ));
+        }
+    }
+
+    problems
+}
+
+#[test]
+fn test_check() {
+    let installed = vec![Package::new(
+        "cargo-backup",
+        semver::Version::parse("0.4.2").unwrap(),
+    )];
+
+    let content = r#"[
+        {"name": "foo", "features": ["a"], "all_features": true, "no_default_features": true, "version": "0.1.0"},
+        {"name": "foo", "features": [], "all_features": false, "no_default_features": false, "version": "0.2.0"},
+        {"name": "b@r", "features": [], "all_features": false, "no_default_features": false, "version": "0.2", "source": "git"}
+    ]"#;
+
+    let problems: Vec<(Severity, String)> = check(content, &installed)
+        .into_iter()
+        .map(|problem| (problem.severity, problem.path))
+        .collect();
+
+    assert_eq!(
+        problems,
+        vec![
+            (Severity::Error, "$[2].name".to_string()),
+            (Severity::Error, "$[2].version".to_string()),
+            (Severity::Warning, "$[0].features".to_string()),
+            (Severity::Warning, "$[0].no_default_features".to_string()),
+            (Severity::Error, "$[1].name".to_string()),
+            (Severity::Warning, "$[2].source".to_string()),
+            (Severity::Warning, "$".to_string()),
+        ]
+    );
+
+    let content = r#"[
+        {"name": "a", "features": [], "all_features": false, "no_default_features": false, "version": "1.0.0", "after": ["b"]},
+        {"name": "b", "features": [], "all_features": false, "no_default_features": false, "version": "1.0.0", "after": ["a", "c"]}
+    ]"#;
+
+    let problems: Vec<(Severity, String)> = check(content, &[])
+        .into_iter()
+        .map(|problem| (problem.severity, problem.path))
+        .collect();
+
+    assert_eq!(
+        problems,
+        vec![
+            (Severity::Warning, "$[1].after[1]".to_string()),
+            (Severity::Error, "$[0].after".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,174 @@
+use owo_colors::{OwoColorize, Style};
+use std::{
+    env,
+    fmt::Display,
+    io::IsTerminal,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// When to color the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color the output written to a terminal, unless `NO_COLOR` is set or forced by `CLICOLOR_FORCE`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("unknown color choice {:?}", s)),
+        }
+    }
+}
+
+/// Where the output goes. Colors and glyphs are chosen for each, e.g. for `cargo restore 2>log`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => std::io::stdout().is_terminal(),
+            Self::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+const UNSET: u8 = 0;
+const ON: u8 = 1;
+const OFF: u8 = 2;
+
+static COLOR: [AtomicU8; 2] = [AtomicU8::new(UNSET), AtomicU8::new(UNSET)];
+static ASCII: [AtomicU8; 2] = [AtomicU8::new(UNSET), AtomicU8::new(UNSET)];
+
+/// Decides if the output is colored.
+/// Until this is called, colors are chosen like [`ColorChoice::Auto`].
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::color::{self, ColorChoice};
+///
+/// color::init(ColorChoice::Never);
+/// ```
+pub fn init(choice: ColorChoice) {
+    for stream in [Stream::Stdout, Stream::Stderr] {
+        let enabled = resolve(
+            choice,
+            env::var("NO_COLOR").ok(),
+            env::var("CLICOLOR_FORCE").ok(),
+            stream.is_terminal(),
+        );
+        COLOR[stream as usize].store(if enabled { ON } else { OFF }, Ordering::Relaxed);
+    }
+}
+
+/// Returns true if the output written to the stream is colored.
+pub fn enabled(stream: Stream) -> bool {
+    if COLOR[stream as usize].load(Ordering::Relaxed) == UNSET {
+        init(ColorChoice::Auto);
+    }
+    COLOR[stream as usize].load(Ordering::Relaxed) == ON
+}
+
+/// Returns true if the trees and results are drawn with ASCII characters,
+/// because the stream is not a terminal or the terminal is dumb.
+pub fn ascii(stream: Stream) -> bool {
+    let ascii = &ASCII[stream as usize];
+    if ascii.load(Ordering::Relaxed) == UNSET {
+        let dumb = !stream.is_terminal() || env::var("TERM").is_ok_and(|term| term == "dumb");
+        ascii.store(if dumb { ON } else { OFF }, Ordering::Relaxed);
+    }
+    ascii.load(Ordering::Relaxed) == ON
+}
+
+fn resolve(
+    choice: ColorChoice,
+    no_color: Option<String>,
+    clicolor_force: Option<String>,
+    is_terminal: bool,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if no_color.is_some_and(|v| !v.is_empty()) => false,
+        ColorChoice::Auto if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") => true,
+        ColorChoice::Auto => is_terminal,
+    }
+}
+
+/// Styles the text printed to stdout if colors are enabled for it.
+pub fn paint(text: impl Display, style: Style) -> String {
+    paint_for(Stream::Stdout, text, style)
+}
+
+/// Styles the text printed to stderr if colors are enabled for it.
+pub fn epaint(text: impl Display, style: Style) -> String {
+    paint_for(Stream::Stderr, text, style)
+}
+
+fn paint_for(stream: Stream, text: impl Display, style: Style) -> String {
+    if enabled(stream) {
+        text.style(style).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// The characters used to draw trees and results.
+pub(crate) struct Glyphs {
+    pub top: &'static str,
+    pub branch: &'static str,
+    pub last: &'static str,
+    pub ok: &'static str,
+    pub failed: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    top: "┌",
+    branch: "├",
+    last: "└",
+    ok: "✔",
+    failed: "✘",
+};
+
+const ASCII_GLYPHS: Glyphs = Glyphs {
+    top: "+",
+    branch: "|",
+    last: "`",
+    ok: "[ok]",
+    failed: "[!!]",
+};
+
+pub(crate) fn glyphs(stream: Stream) -> &'static Glyphs {
+    if ascii(stream) {
+        &ASCII_GLYPHS
+    } else {
+        &UNICODE
+    }
+}
+
+#[test]
+fn test_resolve() {
+    let set = |v: &str| Some(v.to_string());
+
+    assert!(resolve(ColorChoice::Auto, None, None, true));
+    assert!(!resolve(ColorChoice::Auto, None, None, false));
+    assert!(!resolve(ColorChoice::Auto, set("1"), None, true));
+    assert!(resolve(ColorChoice::Auto, set(""), None, true));
+    assert!(resolve(ColorChoice::Auto, None, set("1"), false));
+    assert!(!resolve(ColorChoice::Auto, None, set("0"), false));
+    assert!(resolve(ColorChoice::Always, set("1"), None, false));
+    assert!(!resolve(ColorChoice::Never, None, set("1"), true));
+}
@@ -0,0 +1,110 @@
+use crate::{report, Package};
+use serde::Serialize;
+
+/// A package that exists on both sides of a diff with different options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub from: Package,
+    pub to: Package,
+}
+
+/// The difference between two lists of packages.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff {
+    pub added: Vec<Package>,
+    pub removed: Vec<Package>,
+    pub version_changed: Vec<Change>,
+    pub features_changed: Vec<Change>,
+}
+
+impl Diff {
+    /// Returns true if both sides contain the same packages.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.version_changed.is_empty()
+            && self.features_changed.is_empty()
+    }
+
+    /// Reports the diff, printed as a colored tree by the [`report::HumanReporter`].
+    pub fn print(&self) {
+        report::event(&report::Event::Diff { diff: self });
+    }
+}
+
+/// Compares two lists of packages.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{diff::diff, get_packages};
+///
+/// let drift = diff(&backup, &get_packages());
+/// drift.print();
+/// ```
+pub fn diff(from: &[Package], to: &[Package]) -> Diff {
+    let mut result = Diff::default();
+
+    for package in to {
+        match from.iter().find(|p| p.name == package.name) {
+            None => result.added.push(package.clone()),
+            Some(previous) => {
+                let change = Change {
+                    from: previous.clone(),
+                    to: package.clone(),
+                };
+
+                if previous.version != package.version {
+                    result.version_changed.push(change.clone());
+                }
+
+                if !same_features(previous, package) {
+                    result.features_changed.push(change);
+                }
+            }
+        }
+    }
+
+    for package in from {
+        if !to.iter().any(|p| p.name == package.name) {
+            result.removed.push(package.clone());
+        }
+    }
+
+    result
+}
+
+/// Checks if two packages are installed with the same features, in any order.
+pub(crate) fn same_features(a: &Package, b: &Package) -> bool {
+    let mut a_features = a.features.clone();
+    let mut b_features = b.features.clone();
+    a_features.sort();
+    b_features.sort();
+
+    a_features == b_features
+        && a.all_features == b.all_features
+        && a.no_default_features == b.no_default_features
+}
+
+#[test]
+fn test_diff() {
+    use crate::platform::package;
+
+    let from = vec![
+        package("ripgrep", "13.0.0", &[]),
+        package("bat", "0.24.0", &[]),
+        package("cargo-edit", "0.12.0", &["add", "rm"]),
+    ];
+    let to = vec![
+        package("ripgrep", "14.0.0", &["pcre2"]),
+        package("cargo-edit", "0.12.0", &["rm", "add"]),
+        package("fd-find", "9.0.0", &[]),
+    ];
+
+    let result = diff(&from, &to);
+    assert_eq!(result.added[0].name, "fd-find");
+    assert_eq!(result.removed[0].name, "bat");
+    assert_eq!(result.version_changed.len(), 1);
+    assert_eq!(result.features_changed.len(), 1);
+    assert_eq!(result.features_changed[0].to.name, "ripgrep");
+    assert!(diff(&to, &to).is_empty());
+}
@@ -0,0 +1,191 @@
+use crate::Package;
+use std::{
+    fs::{create_dir_all, read_to_string, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+/// Selects packages by name using `--only` and `--exclude` patterns.
+///
+/// Patterns are either plain package names or globs using `*` and `?`.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::filter::Filter;
+///
+/// let filter = Filter::new(vec!["cargo-*".to_string()], vec!["cargo-backup".to_string()]);
+/// assert!(filter.is_match("cargo-edit"));
+/// assert!(!filter.is_match("cargo-backup"));
+/// assert!(!filter.is_match("ripgrep"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    only: Vec<String>,
+    exclude: Vec<String>,
+    groups: Vec<String>,
+}
+
+impl Filter {
+    pub fn new(only: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            only,
+            exclude,
+            groups: vec![],
+        }
+    }
+
+    /// Only selects packages that are in at least one of the given groups.
+    pub fn with_groups(mut self, groups: Vec<String>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Returns true if no patterns or groups are set and every package matches.
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty() && self.groups.is_empty()
+    }
+
+    /// Checks if the package name is selected by the filter.
+    pub fn is_match(&self, name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|p| glob_match(p, name)))
+            && !self.exclude.iter().any(|p| glob_match(p, name))
+    }
+
+    /// Checks if the package is selected by its name and groups.
+    pub fn is_selected(&self, package: &Package) -> bool {
+        self.is_match(&package.name)
+            && (self.groups.is_empty() || package.groups.iter().any(|g| self.groups.contains(g)))
+    }
+
+    /// Splits the packages into the selected and the filtered packages.
+    pub fn partition(&self, packages: Vec<Package>) -> (Vec<Package>, Vec<Package>) {
+        packages.into_iter().partition(|p| self.is_selected(p))
+    }
+}
+
+/// Returns the path to the ignore file.
+fn get_ignore_path() -> PathBuf {
+    #[cfg(test)]
+    {
+        use std::env;
+        env::current_dir().unwrap().join("tests/ignore")
+    }
+
+    #[cfg(not(test))]
+    {
+        dirs::config_dir()
+            .unwrap()
+            .join("cargo-backup")
+            .join("ignore")
+    }
+}
+
+/// Reads the patterns from the ignore file in the config directory.
+/// Each line contains one pattern, lines starting with `#` are comments.
+pub fn ignored_patterns() -> Vec<String> {
+    let Ok(content) = read_to_string(get_ignore_path()) else {
+        return vec![];
+    };
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Appends the patterns to the ignore file, creating it if needed.
+/// Patterns already in the file are not added again.
+pub fn add_ignored(patterns: &[String]) -> std::io::Result<()> {
+    let path = get_ignore_path();
+    let existing = ignored_patterns();
+    let content = read_to_string(&path).unwrap_or_default();
+
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(file)?;
+    }
+
+    for pattern in patterns {
+        if !existing.contains(pattern) {
+            writeln!(file, "{}", pattern)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Matches a name against a glob pattern supporting `*` and `?`.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("ripgrep", "ripgrep"));
+    assert!(!glob_match("ripgrep", "ripgrep-all"));
+    assert!(glob_match("cargo-*", "cargo-edit"));
+    assert!(glob_match("*-cli", "wasm-bindgen-cli"));
+    assert!(glob_match("c*o-?dit", "cargo-edit"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("cargo-?", "cargo-"));
+}
+
+#[test]
+fn test_filter() {
+    let filter = Filter::new(vec![], vec!["cargo-*".to_string()]);
+    assert!(filter.is_match("ripgrep"));
+    assert!(!filter.is_match("cargo-edit"));
+
+    let filter = Filter::new(vec!["rip*".to_string(), "bat".to_string()], vec![]);
+    assert!(filter.is_match("ripgrep"));
+    assert!(filter.is_match("bat"));
+    assert!(!filter.is_match("fd-find"));
+}
+
+#[test]
+fn test_filter_groups() {
+    let package = |name: &str, groups: &[&str]| Package {
+        groups: groups.iter().map(|g| g.to_string()).collect(),
+        ..crate::platform::package(name, "1.0.0", &[])
+    };
+
+    let filter = Filter::default().with_groups(vec!["core".to_string(), "ci".to_string()]);
+    assert!(filter.is_selected(&package("ripgrep", &["core"])));
+    assert!(filter.is_selected(&package("cargo-nextest", &["web", "ci"])));
+    assert!(!filter.is_selected(&package("trunk", &["web"])));
+    assert!(!filter.is_selected(&package("bat", &[])));
+}
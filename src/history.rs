@@ -0,0 +1,322 @@
+use crate::plan::{Action, Plan};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{create_dir_all, read_to_string, remove_file, rename, write, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The result of a single step of a restore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub package: String,
+    pub action: Action,
+    /// Why the step failed, or `None` if it succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// The file containing the full cargo output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<PathBuf>,
+}
+
+/// A restore recorded in the history log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// When the restore started, in seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The command line of the restore.
+    pub command: Vec<String>,
+    /// The backups that were restored.
+    pub sources: Vec<String>,
+    pub plan: Plan,
+    pub outcomes: Vec<Outcome>,
+    pub duration_ms: u64,
+}
+
+impl Entry {
+    /// Returns the outcomes of the failed steps.
+    pub fn failures(&self) -> impl Iterator<Item = &Outcome> {
+        self.outcomes.iter().filter(|o| o.error.is_some())
+    }
+
+    /// Returns the plan without the steps that already succeeded, so failed steps run again.
+    pub fn remaining(&self) -> Plan {
+        let mut plan = self.plan.clone();
+        plan.retain(|step| {
+            !self.outcomes.iter().any(|o| {
+                o.package == step.package.name && o.action == step.action && o.error.is_none()
+            })
+        });
+        plan
+    }
+
+    /// Records the outcome of a step, replacing the outcome of an earlier attempt.
+    pub fn record(&mut self, outcome: Outcome) {
+        self.outcomes
+            .retain(|o| o.package != outcome.package || o.action != outcome.action);
+        self.outcomes.push(outcome);
+    }
+}
+
+/// Estimates how long steps take from the durations of previous restores.
+#[derive(Debug, Default, Clone)]
+pub struct Estimates {
+    /// The durations of successful steps by package and whether it was a removal, oldest first.
+    durations: Vec<(String, bool, u64)>,
+}
+
+impl Estimates {
+    pub fn new(entries: &[Entry]) -> Self {
+        let durations = entries
+            .iter()
+            .flat_map(|entry| &entry.outcomes)
+            .filter(|outcome| outcome.error.is_none())
+            .map(|outcome| {
+                (
+                    outcome.package.clone(),
+                    outcome.action == Action::Remove,
+                    outcome.duration_ms,
+                )
+            })
+            .collect();
+
+        Self { durations }
+    }
+
+    /// Returns the last duration of the package, or the average duration of all packages.
+    /// Removals are only compared with removals.
+    pub fn get(&self, package: &str, action: Action) -> Option<u64> {
+        let remove = action == Action::Remove;
+        let same_kind: Vec<&(String, bool, u64)> =
+            self.durations.iter().filter(|d| d.1 == remove).collect();
+
+        if let Some(last) = same_kind.iter().rev().find(|d| d.0 == package) {
+            return Some(last.2);
+        }
+
+        if same_kind.is_empty() {
+            return None;
+        }
+
+        Some(same_kind.iter().map(|d| d.2).sum::<u64>() / same_kind.len() as u64)
+    }
+}
+
+/// Returns the directory containing the history and the build logs,
+/// `$CARGO_BACKUP_DATA_DIR` or `cargo-backup` in the data directory.
+fn get_data_dir() -> PathBuf {
+    match std::env::var_os("CARGO_BACKUP_DATA_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir().unwrap().join("cargo-backup"),
+    }
+}
+
+fn get_history_path() -> PathBuf {
+    get_data_dir().join("history.jsonl")
+}
+
+/// The restore that is running or was interrupted, saved after every step.
+fn get_unfinished_path() -> PathBuf {
+    get_data_dir().join("unfinished.json")
+}
+
+/// Saves the state of a running restore, so it can be resumed if it is interrupted.
+pub fn save_unfinished(entry: &Entry) -> std::io::Result<()> {
+    let path = get_unfinished_path();
+    create_dir_all(path.parent().unwrap())?;
+
+    // Replaces the previous state at once, so an interruption never leaves a partial file
+    let temp = path.with_extension("json.tmp");
+    write(&temp, serde_json::to_string(entry)?)?;
+    rename(temp, path)
+}
+
+/// Returns the restore that was interrupted, if any.
+pub fn unfinished() -> Option<Entry> {
+    let content = read_to_string(get_unfinished_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Removes the state of a restore after it finished.
+pub fn clear_unfinished() {
+    let _ = remove_file(get_unfinished_path());
+}
+
+/// Returns a new directory for the build logs of a restore, e.g. `logs/20240131-120000`.
+/// The directory is created when the first log is written.
+pub fn new_log_dir() -> PathBuf {
+    let name = format_timestamp(now())
+        .replace(['-', ':'], "")
+        .replace(' ', "-");
+    get_data_dir().join("logs").join(name)
+}
+
+/// Returns the current time in seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Appends the entry to the history log.
+pub fn append(entry: &Entry) -> std::io::Result<()> {
+    let path = get_history_path();
+    create_dir_all(path.parent().unwrap())?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+/// Reads the history log, oldest first. Unreadable lines are skipped.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::history;
+///
+/// for entry in history::read().iter().filter(|e| e.failures().next().is_some()) {
+///     println!("{}", history::format_timestamp(entry.timestamp));
+/// }
+/// ```
+pub fn read() -> Vec<Entry> {
+    let Ok(content) = read_to_string(get_history_path()) else {
+        return vec![];
+    };
+
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Formats seconds since the unix epoch as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Converts the days since 1970-01-01 to a date of the proleptic gregorian calendar
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[test]
+fn test_format_timestamp() {
+    assert_eq!(format_timestamp(0), "1970-01-01 00:00:00");
+    assert_eq!(format_timestamp(951782400), "2000-02-29 00:00:00");
+    assert_eq!(format_timestamp(1706702400), "2024-01-31 12:00:00");
+}
+
+#[test]
+fn test_estimates() {
+    let outcome = |package: &str, action: Action, duration_ms: u64| Outcome {
+        package: package.to_string(),
+        action,
+        error: None,
+        duration_ms,
+        log: None,
+    };
+    let entry = |outcomes: Vec<Outcome>| Entry {
+        timestamp: 0,
+        command: vec![],
+        sources: vec![],
+        plan: Plan::default(),
+        outcomes,
+        duration_ms: 0,
+    };
+
+    let estimates = Estimates::new(&[
+        entry(vec![
+            outcome("ripgrep", Action::Install, 40_000),
+            outcome("bat", Action::Install, 20_000),
+        ]),
+        entry(vec![
+            outcome("ripgrep", Action::Update, 30_000),
+            outcome("trunk", Action::Remove, 100),
+        ]),
+    ]);
+
+    assert_eq!(estimates.get("ripgrep", Action::Install), Some(30_000));
+    assert_eq!(estimates.get("fd-find", Action::Install), Some(30_000));
+    assert_eq!(estimates.get("bat", Action::Remove), Some(100));
+    assert_eq!(Estimates::default().get("bat", Action::Install), None);
+}
+
+#[test]
+fn test_remaining() {
+    use crate::{
+        plan::PlanOptions,
+        platform::{linux_host, package},
+    };
+
+    let plan = crate::plan::plan(
+        &[package("trunk", "1.0.0", &[])],
+        &[
+            package("ripgrep", "1.0.0", &[]),
+            package("bat", "1.0.0", &[]),
+        ],
+        &PlanOptions::default(),
+        &linux_host(),
+    );
+
+    let entry = Entry {
+        timestamp: 0,
+        command: vec![],
+        sources: vec![],
+        plan,
+        outcomes: vec![
+            Outcome {
+                package: "bat".to_string(),
+                action: Action::Install,
+                error: None,
+                duration_ms: 0,
+                log: None,
+            },
+            Outcome {
+                package: "trunk".to_string(),
+                action: Action::Remove,
+                error: Some("cargo exited with status 101".to_string()),
+                duration_ms: 0,
+                log: None,
+            },
+        ],
+        duration_ms: 0,
+    };
+
+    let remaining = entry.remaining();
+    let steps: Vec<&str> = remaining
+        .steps()
+        .map(|step| step.package.name.as_str())
+        .collect();
+    assert_eq!(steps, vec!["ripgrep", "trunk"]);
+
+    // The retried step replaces its failure
+    let mut entry = entry;
+    entry.record(Outcome {
+        package: "trunk".to_string(),
+        action: Action::Remove,
+        error: None,
+        duration_ms: 0,
+        log: None,
+    });
+    assert_eq!(entry.outcomes.len(), 2);
+    assert!(entry.failures().next().is_none());
+}
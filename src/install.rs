@@ -0,0 +1,106 @@
+use crate::{
+    remote::{get_config, ProviderConfig},
+    Package,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How packages are installed, loaded from `install.toml` in the config directory.
+///
+/// # Examples
+/// ```toml
+/// args = ["--locked"]
+/// allowed_args = ["--locked", "--offline", "--frozen", "--target"]
+/// allowed_env = ["OPENSSL_STATIC"]
+///
+/// [env]
+/// RUSTFLAGS = "-C target-cpu=native"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Arguments passed to every `cargo install`, before the arguments of the package.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The arguments a backup may set without confirmation.
+    /// `--target` also allows `--target=<value>`.
+    #[serde(default)]
+    pub allowed_args: Vec<String>,
+    /// The environment variables a backup may set without confirmation.
+    #[serde(default)]
+    pub allowed_env: Vec<String>,
+    /// Environment variables set for every `cargo install`, unless the package sets them too.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            args: vec![],
+            allowed_args: vec![
+                "--locked".to_string(),
+                "--offline".to_string(),
+                "--frozen".to_string(),
+            ],
+            allowed_env: vec![],
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProviderConfig for Config {
+    fn get_name() -> String {
+        String::from("install")
+    }
+}
+
+impl Config {
+    /// Loads the install config from the config directory.
+    pub fn load() -> Self {
+        get_config()
+    }
+
+    /// Returns the arguments and environment variables of the package that are not allowed,
+    /// e.g. `--git=https://example.com/fork` or `RUSTFLAGS`.
+    pub fn disallowed(&self, package: &Package) -> Vec<String> {
+        let args = package.args.iter().filter(|arg| {
+            !self
+                .allowed_args
+                .iter()
+                .any(|allowed| *arg == allowed || arg.starts_with(&format!("{}=", allowed)))
+        });
+        let env = package
+            .env
+            .keys()
+            .filter(|key| !self.allowed_env.contains(key));
+
+        args.chain(env).cloned().collect()
+    }
+}
+
+#[test]
+fn test_disallowed() {
+    let package = Package {
+        args: vec![
+            "--locked".to_string(),
+            "--target=x86_64-unknown-linux-musl".to_string(),
+            "--git=https://example.com/fork".to_string(),
+        ],
+        env: BTreeMap::from([
+            ("OPENSSL_STATIC".to_string(), "1".to_string()),
+            ("RUSTFLAGS".to_string(), "-C linker=evil".to_string()),
+        ]),
+        ..Package::new("ripgrep", semver::Version::new(14, 0, 0))
+    };
+
+    let config = Config {
+        allowed_args: vec!["--locked".to_string(), "--target".to_string()],
+        allowed_env: vec!["OPENSSL_STATIC".to_string()],
+        ..Config::default()
+    };
+
+    assert_eq!(
+        config.disallowed(&package),
+        vec!["--git=https://example.com/fork", "RUSTFLAGS"]
+    );
+}
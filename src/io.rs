@@ -0,0 +1,200 @@
+use crate::sign;
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+/// The path used to refer to stdin or stdout.
+pub const STDIO: &str = "-";
+
+/// Where a backup is read from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    Stdin,
+    Url(String),
+    File(PathBuf),
+}
+
+impl Source {
+    /// Parses the source from a path, `-` or a `http(s)://` / `file://` URL.
+    /// `~` and environment variables are expanded for paths.
+    pub fn parse(path: &str) -> Result<Self, Box<dyn Error>> {
+        if path == STDIO {
+            Ok(Self::Stdin)
+        } else if path.starts_with("https://") || path.starts_with("http://") {
+            Ok(Self::Url(path.to_string()))
+        } else if let Some(path) = path.strip_prefix("file://") {
+            Ok(Self::File(PathBuf::from(path)))
+        } else {
+            Ok(Self::File(expand_path(path)?))
+        }
+    }
+
+    /// Reads the content of the source.
+    pub fn read(&self) -> Result<String, Box<dyn Error>> {
+        match self {
+            Self::Stdin => {
+                let mut content = String::new();
+                io::stdin().read_to_string(&mut content)?;
+                Ok(content)
+            }
+            Self::Url(url) => Ok(ureq::get(url)
+                .set(
+                    "User-Agent",
+                    &format!("CargoBackup/{}", env!("CARGO_PKG_VERSION")),
+                )
+                .call()
+                .map_err(describe_http_error)?
+                .into_string()?),
+            Self::File(path) => Ok(fs::read_to_string(path)?),
+        }
+    }
+
+    /// Checks if the source is fetched over plain `http://`, where it can be changed in transit.
+    pub fn is_insecure(&self) -> bool {
+        matches!(self, Self::Url(url) if url.starts_with("http://"))
+    }
+
+    /// Reads the sidecar signature (`<source>.sig`) of the source, if there is one.
+    pub fn read_signature(&self) -> Option<String> {
+        match self {
+            Self::Stdin => None,
+            Self::Url(url) => Self::Url(format!("{}.sig", url)).read().ok(),
+            Self::File(path) => fs::read_to_string(sign::signature_path(path)).ok(),
+        }
+    }
+}
+
+/// Describes the error without the url, which is reported with the source.
+fn describe_http_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, _) => format!("the server responded with status {}", code),
+        ureq::Error::Transport(e) => {
+            let message = e.to_string();
+            match e.url() {
+                Some(url) => message.replacen(&format!("{}: ", url), "", 1),
+                None => message,
+            }
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "stdin"),
+            Self::Url(url) => write!(f, "{}", url),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChecksumMismatch {
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checksum mismatch: expected sha256 {}, got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl Error for ChecksumMismatch {}
+
+/// Expands `~` and environment variables in the given path.
+///
+/// # Examples
+/// ```no_run
+/// let path = expand_path("~/backup.json").unwrap();
+/// ```
+pub fn expand_path(path: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(PathBuf::from(shellexpand::full(path)?.as_ref()))
+}
+
+/// Reads a backup from a path, a URL, or from stdin if the path is `-`.
+pub fn read_backup(path: &str) -> Result<String, Box<dyn Error>> {
+    Source::parse(path)?.read()
+}
+
+/// Writes a backup to the given path, or to stdout if the path is `-`.
+pub fn write_backup(path: &str, content: &str) -> Result<(), Box<dyn Error>> {
+    if path == STDIO {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    Ok(fs::write(expand_path(path)?, content)?)
+}
+
+/// Returns the hex encoded sha256 checksum of the content.
+pub fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Checks that the content matches the pinned sha256 checksum.
+pub fn verify_checksum(content: &[u8], expected: &str) -> Result<(), ChecksumMismatch> {
+    let actual = sha256(content);
+    let expected = expected.trim().trim_start_matches("sha256:").to_lowercase();
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ChecksumMismatch { expected, actual })
+    }
+}
+
+#[test]
+fn test_expand_path() {
+    std::env::set_var("CARGO_BACKUP_TEST_DIR", "/tmp/backups");
+
+    assert_eq!(
+        expand_path("$CARGO_BACKUP_TEST_DIR/backup.json").unwrap(),
+        PathBuf::from("/tmp/backups/backup.json")
+    );
+    assert_eq!(
+        expand_path("~/backup.json").unwrap(),
+        dirs::home_dir().unwrap().join("backup.json")
+    );
+}
+
+#[test]
+fn test_source_parse() {
+    assert_eq!(Source::parse("-").unwrap(), Source::Stdin);
+    assert_eq!(
+        Source::parse("https://example.com/backup.json").unwrap(),
+        Source::Url("https://example.com/backup.json".to_string())
+    );
+    assert!(!Source::parse("https://example.com/backup.json")
+        .unwrap()
+        .is_insecure());
+    assert!(Source::parse("http://example.com/backup.json")
+        .unwrap()
+        .is_insecure());
+    assert_eq!(
+        Source::parse("file:///tmp/backup.json").unwrap(),
+        Source::File(PathBuf::from("/tmp/backup.json"))
+    );
+}
+
+#[test]
+fn test_verify_checksum() {
+    let checksum = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    assert!(verify_checksum(b"hello", checksum).is_ok());
+    assert!(verify_checksum(b"hello", &format!("sha256:{}", checksum.to_uppercase())).is_ok());
+    assert!(verify_checksum(b"hello!", checksum).is_err());
+}
@@ -1,11 +1,43 @@
-use misc::{pretty_print_packages, Errors};
-use owo_colors::OwoColorize;
+use filter::{ignored_patterns, Filter};
+use history::Outcome;
+use misc::Errors;
+use plan::{Action, Plan, PlanOptions, Step};
+use platform::{Host, Override};
+use progress::Progress;
+use runner::Runner;
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, vec};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+    vec,
+};
 
+pub mod check;
+pub mod color;
+pub mod diff;
+pub mod filter;
+pub mod history;
+pub mod install;
+pub mod io;
+pub mod lock;
+pub mod merge;
 mod misc;
+pub mod order;
+pub mod plan;
+pub mod platform;
+pub mod profile;
+mod progress;
 pub mod remote;
+pub mod report;
+pub mod runner;
+pub mod schema;
+pub mod sign;
+pub mod toolchain;
 mod url;
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
@@ -15,6 +47,65 @@ pub struct Package {
     pub all_features: bool,
     pub no_default_features: bool,
     pub version: Version,
+    /// The groups of the package, e.g. `core` or `ci`, used to restore only a subset.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// Only install the package on platforms matching the `cfg(...)` expression or target triple pattern.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_on: Option<String>,
+    /// Per platform overrides of the install options.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<Override>,
+    /// Extra arguments passed to `cargo install`, e.g. `--locked`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Environment variables set for `cargo install`, e.g. `OPENSSL_STATIC=1`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// The rustup toolchain the package is installed with, e.g. `nightly` or `1.75.0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    /// Packages with a higher priority are installed first, e.g. `cargo-binstall`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    /// The packages that have to be installed or updated before this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+impl Package {
+    /// Creates a package with the default features and none of the fields that only exist in backups.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use cargo_backup::Package;
+    ///
+    /// let package = Package {
+    ///     features: vec!["pcre2".to_string()],
+    ///     ..Package::new("ripgrep", semver::Version::new(14, 0, 0))
+    /// };
+    /// ```
+    pub fn new(name: &str, version: Version) -> Self {
+        Self {
+            name: name.to_string(),
+            features: vec![],
+            all_features: false,
+            no_default_features: false,
+            version,
+            groups: vec![],
+            only_on: None,
+            overrides: vec![],
+            args: vec![],
+            env: BTreeMap::new(),
+            toolchain: None,
+            priority: 0,
+            after: vec![],
+        }
+    }
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -28,6 +119,17 @@ struct Install {
     pub features: Vec<String>,
     pub no_default_features: bool,
     pub all_features: bool,
+    /// The version of rustc the package was built with.
+    #[serde(default)]
+    pub rustc: Option<String>,
+}
+
+/// Returns the cargo home the packages are installed to, `$CARGO_HOME` or `~/.cargo`.
+pub(crate) fn get_cargo_home() -> PathBuf {
+    match std::env::var_os("CARGO_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => dirs::home_dir().unwrap().join(".cargo"),
+    }
 }
 
 /// Returns the path to the .crates2.json file.
@@ -40,13 +142,14 @@ fn get_crates_path() -> PathBuf {
 
     #[cfg(not(test))]
     {
-        let path = dirs::home_dir().unwrap().join(".cargo/.crates2.json");
+        let path = get_cargo_home().join(".crates2.json");
         assert!(path.exists());
         path
     }
 }
 
-/// Gets the currently installed packages from the .crates2.json file.
+/// Gets the currently installed packages from the .crates2.json file, sorted by name.
+/// Packages matching a pattern in the ignore file are skipped.
 ///
 /// # Examples
 /// ```no_run
@@ -66,6 +169,7 @@ pub fn get_packages() -> Vec<Package> {
     .unwrap_or_else(|_| panic!("{}", Errors::JsonParse));
 
     let mut packages = vec![];
+    let ignored = Filter::new(vec![], ignored_patterns());
 
     for (id, install) in crates.installs {
         let (name, version, skip) = slice_info(&id);
@@ -74,80 +178,619 @@ pub fn get_packages() -> Vec<Package> {
             continue;
         }
 
+        if !ignored.is_match(&name) {
+            report::info(format!(
+                "{} ignored because it matches the ignore file",
+                name
+            ));
+            continue;
+        }
+
         packages.push(Package {
-            name: name.to_string(),
             features: install.features,
             all_features: install.all_features,
             no_default_features: install.no_default_features,
-            version,
+            toolchain: install.rustc.as_deref().and_then(toolchain::from_rustc),
+            ..Package::new(&name, version)
         });
     }
 
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
     packages
 }
 
+/// Copies the fields that only exist in backups (groups, platform conditions, install arguments, environment and ordering)
+/// from a previous backup to the packages with the same name.
+/// The toolchain of the previous backup is kept as well, if it sets one.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{apply_previous, get_packages};
+///
+/// let mut packages = get_packages();
+/// apply_previous(&mut packages, &previous_backup);
+/// ```
+pub fn apply_previous(packages: &mut [Package], previous: &[Package]) {
+    for package in packages {
+        if let Some(p) = previous.iter().find(|p| p.name == package.name) {
+            package.groups = p.groups.clone();
+            package.only_on = p.only_on.clone();
+            package.overrides = p.overrides.clone();
+            package.args = p.args.clone();
+            package.env = p.env.clone();
+            package.priority = p.priority;
+            package.after = p.after.clone();
+            if p.toolchain.is_some() {
+                package.toolchain = p.toolchain.clone();
+            }
+        }
+    }
+}
+
+/// Options for [`install_packages`].
+#[derive(Debug, Default, Clone)]
+pub struct RestoreOptions {
+    /// Which packages are installed, updated and removed.
+    pub plan: PlanOptions,
+    /// Only print the plan without installing or removing anything.
+    pub dry_run: bool,
+    /// Which steps need to be confirmed before they are executed.
+    pub confirm: Confirmation,
+    /// The backups the packages come from, recorded in the history.
+    pub sources: Vec<String>,
+    /// Wait for another restore to finish instead of failing.
+    pub wait: bool,
+}
+
+/// Which steps of a restore need to be confirmed by the user.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Ask once before executing any step.
+    #[default]
+    Always,
+    /// Execute installs and updates right away, but ask before removing packages.
+    RemovalsOnly,
+    /// Never ask, e.g. for CI and provisioning scripts.
+    Never,
+    /// Let the user pick the steps to execute.
+    /// Deselected packages can be added to the ignore file.
+    Select,
+}
+
+#[derive(Debug)]
+pub enum RestoreError {
+    /// A confirmation is required but there is no terminal to ask.
+    NotInteractive,
+    /// The confirmation prompt failed.
+    Prompt(String),
+    /// The deselected packages could not be added to the ignore file.
+    IgnoreFile(String),
+    /// The steps of the packages failed.
+    Failed(Vec<String>),
+    /// The restore was stopped with [`interrupt`] and can be continued with [`resume_restore`].
+    Interrupted,
+    /// There is no interrupted restore to resume.
+    NothingToResume,
+    /// The install root could not be locked, e.g. because another restore is running.
+    Lock(lock::LockError),
+    /// The backup sets install arguments or environment variables that are not allowed
+    /// by the [`install::Config`] and were not confirmed.
+    NotAllowed(Vec<String>),
+    /// The toolchain of a package is not installed and was not installed either.
+    MissingToolchain(String),
+    /// The toolchains could not be checked or installed with rustup.
+    Toolchain(toolchain::ToolchainError),
+    /// The packages to install depend on each other in a cycle.
+    Cycle(order::CycleError),
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInteractive => write!(
+                f,
+                "cannot ask for confirmation because the terminal is not interactive, use --yes to restore without confirmation"
+            ),
+            Self::Prompt(e) => write!(f, "failed to ask for confirmation: {}", e),
+            Self::IgnoreFile(e) => write!(f, "failed to update the ignore file: {}", e),
+            Self::Failed(names) => write!(
+                f,
+                "failed to restore {} package(s): {}",
+                names.len(),
+                names.join(", ")
+            ),
+            Self::Interrupted => write!(
+                f,
+                "the restore was interrupted, continue it with cargo restore --resume"
+            ),
+            Self::NothingToResume => write!(f, "there is no interrupted restore to resume"),
+            Self::Lock(e) => write!(f, "{}", e),
+            Self::NotAllowed(extras) => write!(
+                f,
+                "not allowed to install with {}, add them to allowed_args or allowed_env in install.toml",
+                extras.join(", ")
+            ),
+            Self::MissingToolchain(toolchain) => write!(
+                f,
+                "toolchain {} is not installed, install it with rustup toolchain install {}",
+                toolchain, toolchain
+            ),
+            Self::Toolchain(e) => write!(f, "{}", e),
+            Self::Cycle(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RestoreError {}
+
+/// Installs, updates and removes packages so the installed packages match the backup.
+/// Packages not selected by the filter are neither installed, updated nor removed.
+/// Packages whose platform conditions do not match the host or that match the ignore file are skipped.
+/// The steps are ordered by the `priority` and `after` fields of the packages with [`Plan::order`].
+///
+/// Prints the [`Plan`] and applies it with [`apply_plan`] unless it is a dry run.
+/// Applied plans are recorded in the [`history`].
+/// The state is saved after every step, so an interrupted restore can be continued with [`resume_restore`].
+/// Returns the plan.
+///
+/// # Errors
+/// * If a confirmation is required but the terminal is not interactive.
+///   Nothing is executed in this case.
+/// * If any step failed.
+/// * If the restore was stopped with [`interrupt`].
+/// * If another restore is running, unless [`RestoreOptions::wait`] is set.
+/// * If the backup sets install arguments or environment variables that are not allowed
+///   by the [`install::Config`] and the user does not confirm them.
+/// * If the toolchain of a package is missing and not installed.
+/// * If the packages depend on each other in a cycle. Nothing is executed in this case.
 pub fn install_packages(
     packages: &[Package],
-    skip_install: bool,
-    skip_update: bool,
-    skip_remove: bool,
-) {
-    let installed_packages = get_packages();
-
-    let mut to_update: Vec<Package> = vec![];
-    let mut to_install: Vec<Package> = vec![];
-    let mut to_remove: Vec<Package> = vec![];
-
-    if !skip_install {
-        for package in packages {
-            if !installed_packages.iter().any(|p| p.name == package.name) {
-                to_install.push(package.clone());
-            }
+    options: &RestoreOptions,
+    runner: &mut dyn Runner,
+) -> Result<Plan, RestoreError> {
+    // Locked before planning, so the plan starts from what the other restore left behind
+    let _lock = lock_unless_dry_run(options)?;
+    let (packages, ignored) = Filter::new(vec![], ignored_patterns()).partition(packages.to_vec());
+
+    let mut plan = plan::plan(&get_packages(), &packages, &options.plan, &Host::current());
+    for package in ignored {
+        if !plan.skipped.iter().any(|p| p.name == package.name) {
+            plan.skipped.push(package);
         }
     }
+    plan.order().map_err(RestoreError::Cycle)?;
 
-    if !skip_update {
-        for package in &installed_packages {
-            if let Some(p) = packages.iter().find(|np| np.name == package.name) {
-                if p.version > package.version {
-                    to_update.push(p.clone());
-                }
-            }
+    report::event(&report::Event::Plan {
+        plan: &plan,
+        dry_run: options.dry_run,
+        confirm: !options.dry_run && needs_confirmation(&plan, options.confirm),
+    });
+
+    let disallowed: Vec<String> = plan
+        .steps()
+        .filter(|step| step.action != Action::Remove)
+        .flat_map(|step| {
+            options
+                .plan
+                .install
+                .disallowed(&step.package)
+                .into_iter()
+                .map(|extra| format!("{}: {}", step.package.name, extra))
+        })
+        .collect();
+
+    if !disallowed.is_empty() {
+        report::warn(format!(
+            "The backup sets install arguments or environment variables that are not allowed:\n  {}",
+            disallowed.join("\n  ")
+        ));
+
+        let confirmed =
+            options.dry_run || is_interactive() && confirm("Install with them anyway?")?;
+        if !confirmed {
+            return Err(RestoreError::NotAllowed(disallowed));
         }
     }
 
-    if !skip_remove {
-        for package in &installed_packages {
-            if !packages.iter().any(|np| np.name == package.name) {
-                to_remove.push(package.clone());
+    if options.dry_run {
+        return Ok(plan);
+    }
+
+    let entry = history::Entry {
+        timestamp: history::now(),
+        command: std::env::args().collect(),
+        sources: options.sources.clone(),
+        plan: plan.clone(),
+        outcomes: vec![],
+        duration_ms: 0,
+    };
+    record_plan(entry, &plan, options.confirm, runner)?;
+
+    Ok(plan)
+}
+
+/// Continues the interrupted restore with the steps that did not run yet.
+/// The plan is not recomputed, so the steps are the same as when the restore started.
+///
+/// Prints the remaining [`Plan`] and applies it unless it is a dry run. Returns the remaining plan.
+/// Only the confirmation and dry run of the options are used.
+///
+/// # Errors
+/// * If there is no interrupted restore.
+/// * The same as [`install_packages`].
+pub fn resume_restore(
+    options: &RestoreOptions,
+    runner: &mut dyn Runner,
+) -> Result<Plan, RestoreError> {
+    let _lock = lock_unless_dry_run(options)?;
+    let entry = history::unfinished().ok_or(RestoreError::NothingToResume)?;
+    let plan = entry.remaining();
+
+    report::info(format!(
+        "Resuming the restore from {}",
+        history::format_timestamp(entry.timestamp)
+    ));
+    report::event(&report::Event::Plan {
+        plan: &plan,
+        dry_run: options.dry_run,
+        confirm: !options.dry_run && needs_confirmation(&plan, options.confirm),
+    });
+
+    if !options.dry_run {
+        record_plan(entry, &plan, options.confirm, runner)?;
+    }
+
+    Ok(plan)
+}
+
+/// Makes sure the toolchains of the packages to install are installed,
+/// installing missing ones with rustup after asking for confirmation as configured.
+fn ensure_toolchains(plan: &Plan, confirm: Confirmation) -> Result<(), RestoreError> {
+    let mut toolchains: Vec<&str> = plan
+        .steps()
+        .filter(|step| step.action != Action::Remove)
+        .filter_map(|step| step.package.toolchain.as_deref())
+        .collect();
+    toolchains.sort();
+    toolchains.dedup();
+
+    if toolchains.is_empty() {
+        return Ok(());
+    }
+
+    let triple = Host::current().triple;
+    for toolchain in toolchains {
+        if toolchain::is_installed(toolchain, &triple).map_err(RestoreError::Toolchain)? {
+            continue;
+        }
+
+        let install = match confirm {
+            Confirmation::Never => true,
+            _ => {
+                is_interactive()
+                    && self::confirm(&format!(
+                        "Toolchain {} is not installed. Install it with rustup?",
+                        toolchain
+                    ))?
             }
+        };
+        if !install {
+            return Err(RestoreError::MissingToolchain(toolchain.to_string()));
         }
+
+        report::info(format!("Installing toolchain {}", toolchain));
+        toolchain::install(toolchain).map_err(RestoreError::Toolchain)?;
     }
 
-    pretty_print_packages(&to_install.clone(), &to_update.clone(), &to_remove.clone());
+    Ok(())
+}
 
-    // Skip the Installation process if it is a test
-    #[cfg(not(test))]
-    {
-        use crate::misc::{execute_cmd, CommandType};
-        use dialoguer::Confirm;
+fn lock_unless_dry_run(options: &RestoreOptions) -> Result<Option<lock::Lock>, RestoreError> {
+    if options.dry_run {
+        return Ok(None);
+    }
+    lock::acquire(options.wait)
+        .map(Some)
+        .map_err(RestoreError::Lock)
+}
 
-        if Confirm::new().with_prompt("Proceed?").interact().unwrap() {
-            // TODO: Install
+/// Applies the plan while saving the entry as the unfinished restore,
+/// and appends it to the history once the restore is over.
+///
+/// The state is only replaced once the plan is confirmed, and it is kept if the restore stops
+/// before it is over, so a declined or failed prompt never loses an interrupted restore.
+fn record_plan(
+    mut entry: history::Entry,
+    plan: &Plan,
+    confirm: Confirmation,
+    runner: &mut dyn Runner,
+) -> Result<(), RestoreError> {
+    let save = |entry: &history::Entry| {
+        if let Err(e) = history::save_unfinished(entry) {
+            report::warn(format!("Failed to save the restore state: {}", e));
+        }
+    };
+
+    reset_interrupt();
+    let Some(confirmed) = confirm_plan(plan, confirm)? else {
+        return Ok(());
+    };
+    // Only the toolchains of the steps the user agreed to are installed
+    ensure_toolchains(&confirmed.plan, confirm)?;
+
+    if let Some(unfinished) = history::unfinished().filter(|unfinished| *unfinished != entry) {
+        report::warn(format!(
+            "Discarding the interrupted restore from {}",
+            history::format_timestamp(unfinished.timestamp)
+        ));
+    }
+
+    let started = Instant::now();
+    let previous_ms = entry.duration_ms;
+    save(&entry);
+    let result = execute_plan(&confirmed, confirm, runner, &mut |outcome| {
+        entry.record(outcome.clone());
+        entry.duration_ms = previous_ms + started.elapsed().as_millis() as u64;
+        save(&entry);
+    });
+
+    // Stopped before the end, the remaining steps can still be resumed
+    if matches!(
+        result,
+        Err(RestoreError::Interrupted | RestoreError::Prompt(_))
+    ) {
+        return result;
+    }
+    history::clear_unfinished();
+    result?;
+
+    if !entry.outcomes.is_empty() {
+        entry.duration_ms = previous_ms + started.elapsed().as_millis() as u64;
+
+        if let Err(e) = history::append(&entry) {
+            report::warn(format!("Failed to write the history: {}", e));
+        }
+
+        let failed: Vec<String> = entry.failures().map(|o| o.package.clone()).collect();
+        if !failed.is_empty() {
+            return Err(RestoreError::Failed(failed));
+        }
+    }
+
+    Ok(())
+}
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Stops the running restore once the current step is over. Call this from a signal handler.
+///
+/// A step whose cargo process is cancelled by the same signal is not recorded,
+/// so it runs again when the restore is resumed.
+/// Every restore starts uninterrupted, so a signal only stops the restore that is running.
+///
+/// # Examples
+/// ```no_run
+/// ctrlc::set_handler(cargo_backup::interrupt).unwrap();
+/// ```
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+fn reset_interrupt() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Executes the steps of the plan with the runner, asking for confirmation as configured.
+/// A failing step does not stop the remaining steps.
+///
+/// Returns the outcome of every executed step.
+///
+/// # Errors
+/// * If a confirmation is required but the terminal is not interactive.
+///   Nothing is executed in this case.
+/// * If the prompt fails.
+/// * If the restore was stopped with [`interrupt`]. The outcomes of the finished steps are lost.
+pub fn apply_plan(
+    plan: &Plan,
+    confirm: Confirmation,
+    runner: &mut dyn Runner,
+) -> Result<Vec<Outcome>, RestoreError> {
+    reset_interrupt();
+    let Some(confirmed) = confirm_plan(plan, confirm)? else {
+        return Ok(vec![]);
+    };
+
+    let mut outcomes = vec![];
+    execute_plan(&confirmed, confirm, runner, &mut |outcome| {
+        outcomes.push(outcome.clone())
+    })?;
+    Ok(outcomes)
+}
+
+/// The steps of a plan the user agreed to run.
+struct Confirmed<'a> {
+    plan: std::borrow::Cow<'a, Plan>,
+    /// The packages the user deselected with [`Confirmation::Select`].
+    deselected: Vec<String>,
+}
+
+/// Checks if the user has to confirm anything before or while the plan runs.
+fn needs_confirmation(plan: &Plan, confirm: Confirmation) -> bool {
+    match confirm {
+        Confirmation::Always | Confirmation::Select => !plan.is_empty(),
+        Confirmation::RemovalsOnly => !plan.removals.is_empty(),
+        Confirmation::Never => false,
+    }
+}
 
-            for package in to_install {
-                execute_cmd(&package, CommandType::Install);
+/// Asks the user which steps to run, or if the plan should run at all.
+/// Returns `None` if the user declined the plan.
+fn confirm_plan(plan: &Plan, confirm: Confirmation) -> Result<Option<Confirmed<'_>>, RestoreError> {
+    if needs_confirmation(plan, confirm) && !is_interactive() {
+        return Err(RestoreError::NotInteractive);
+    }
+
+    let mut confirmed = Confirmed {
+        plan: std::borrow::Cow::Borrowed(plan),
+        deselected: vec![],
+    };
+
+    // There is nothing to confirm or select, and dialoguer refuses to select from an empty list
+    if plan.is_empty() {
+        return Ok(Some(confirmed));
+    }
+
+    match confirm {
+        Confirmation::Always => {
+            if !self::confirm("Proceed?")? {
+                return Ok(None);
             }
+        }
+        Confirmation::Select => {
+            let selected = select(plan)?;
+            confirmed.deselected = plan
+                .steps()
+                .map(|step| step.package.name.clone())
+                .filter(|name| !selected.contains(name))
+                .collect();
+            confirmed
+                .plan
+                .to_mut()
+                .retain(|step| selected.contains(&step.package.name));
+        }
+        Confirmation::RemovalsOnly | Confirmation::Never => {}
+    }
+
+    Ok(Some(confirmed))
+}
 
-            for package in to_update {
-                execute_cmd(&package, CommandType::Install);
+/// Runs the confirmed steps, passing each outcome to the callback as soon as the step is over.
+/// With [`Confirmation::RemovalsOnly`], the removals are confirmed after the other steps ran.
+fn execute_plan(
+    confirmed: &Confirmed,
+    confirm: Confirmation,
+    runner: &mut dyn Runner,
+    on_outcome: &mut dyn FnMut(&Outcome),
+) -> Result<(), RestoreError> {
+    let plan = &confirmed.plan;
+    let mut progress = Progress::new(plan.steps(), &history::Estimates::new(&history::read()));
+    let mut execute = |steps: Vec<&Step>| {
+        for step in steps {
+            if is_interrupted() {
+                return Err(RestoreError::Interrupted);
             }
 
-            for package in to_remove {
-                execute_cmd(&package, CommandType::Remove);
+            progress.start(step);
+            report::debug(format!("Running {}", step.command.join(" ")));
+            let started = Instant::now();
+            let log = runner.log_path(step);
+            let error = runner.run(step).err().map(|e| match &log {
+                Some(log) => format!("{}, see {}", e, log.display()),
+                None => e.to_string(),
+            });
+
+            let duration_ms = started.elapsed().as_millis() as u64;
+
+            // The cargo process was most likely cancelled by the same signal
+            if error.is_some() && is_interrupted() {
+                report::warn(format!("Cancelled {}", step.package.name));
+                return Err(RestoreError::Interrupted);
             }
+
+            report::event(&report::Event::Step {
+                step,
+                error: error.clone(),
+                duration_ms,
+            });
+            on_outcome(&Outcome {
+                package: step.package.name.clone(),
+                action: step.action,
+                error,
+                duration_ms,
+                log,
+            });
         }
+
+        Ok(())
+    };
+
+    let removals: Vec<&Step> = plan.removals.iter().collect();
+    let changes: Vec<&Step> = plan
+        .steps()
+        .filter(|step| step.action != Action::Remove)
+        .collect();
+
+    execute(changes)?;
+    if confirm == Confirmation::RemovalsOnly
+        && !removals.is_empty()
+        && !self::confirm(&format!("Remove {} package(s)?", removals.len()))?
+    {
+        return Ok(());
     }
+    execute(removals)?;
+
+    if !confirmed.deselected.is_empty()
+        && self::confirm(&format!(
+            "Add the {} deselected package(s) to the ignore file?",
+            confirmed.deselected.len()
+        ))?
+    {
+        filter::add_ignored(&confirmed.deselected)
+            .map_err(|e| RestoreError::IgnoreFile(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Checks if the user can be asked for confirmation.
+fn is_interactive() -> bool {
+    use std::io::IsTerminal;
+
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+fn confirm(prompt: &str) -> Result<bool, RestoreError> {
+    dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .interact()
+        .map_err(|e| RestoreError::Prompt(e.to_string()))
+}
+
+/// Asks the user which steps to execute and returns the names of the selected packages.
+fn select(plan: &Plan) -> Result<Vec<String>, RestoreError> {
+    let steps: Vec<&Step> = plan.steps().collect();
+    let items: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            let action = match step.action {
+                Action::Install => "install",
+                Action::Update => "update",
+                Action::Downgrade => "downgrade",
+                Action::Reconfigure => "reconfigure",
+                Action::Remove => "remove",
+            };
+            format!(
+                "{} {} {} ({})",
+                action, step.package.name, step.package.version, step.reason
+            )
+        })
+        .collect();
+
+    let selection = dialoguer::MultiSelect::new()
+        .with_prompt("Select the packages to apply (space to toggle, enter to confirm)")
+        .items(&items)
+        .defaults(&vec![true; items.len()])
+        .interact()
+        .map_err(|e| RestoreError::Prompt(e.to_string()))?;
+
+    Ok(selection
+        .into_iter()
+        .map(|i| steps[i].package.name.clone())
+        .collect())
 }
 
 /// Gets the Package name and Version and from the string.
@@ -163,11 +806,10 @@ fn slice_info(package_str: &str) -> (String, Version, bool) {
     let version = Version::parse(splits[1]).unwrap();
     let local_package = splits[2].contains("path+file://") || splits[2].contains("git+https://");
     if local_package {
-        println!(
-            "{} {}",
-            name.blue(),
-            "Package ignored because it is either a local or git Package".red()
-        );
+        report::info(format!(
+            "{} ignored because it is either a local or git Package",
+            name
+        ));
     }
     (name, version, local_package)
 }
@@ -203,19 +845,46 @@ fn test_install_packages() {
     let fake_packages: Vec<Package> = vec![
         Package {
             all_features: true,
-            features: vec![],
-            name: "foo".to_string(),
-            no_default_features: false,
-            version: Version::parse("0.1.0").unwrap(),
+            ..Package::new("foo", Version::parse("0.1.0").unwrap())
         },
         Package {
-            name: "package".to_string(),
-            version: Version::parse("0.5.3").unwrap(),
-            all_features: false,
-            no_default_features: false,
             features: vec!["feature1".to_string(), "feature2".to_string()],
+            groups: vec!["core".to_string()],
+            ..Package::new("package", Version::parse("0.5.3").unwrap())
         },
     ];
 
-    install_packages(&fake_packages, false, false, false);
+    let plan = install_packages(
+        &fake_packages,
+        &RestoreOptions {
+            dry_run: true,
+            ..Default::default()
+        },
+        &mut runner::RecordingRunner::default(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        plan.steps()
+            .map(|step| (step.action, step.package.name.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (Action::Install, "foo"),
+            (Action::Update, "package"),
+            (Action::Remove, "even-cooler-thing"),
+            (Action::Remove, "super-cool-thing"),
+        ]
+    );
+    assert_eq!(
+        plan.updates[0].command,
+        vec![
+            "cargo",
+            "install",
+            "package",
+            "--version",
+            "0.5.3",
+            "--features",
+            "feature1,feature2"
+        ]
+    );
 }
@@ -0,0 +1,124 @@
+use crate::report;
+use fs4::fs_std::FileExt;
+use std::{
+    error::Error,
+    fmt,
+    fs::{create_dir_all, File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+/// Keeps other cargo-backup processes from installing or removing packages until it is dropped.
+///
+/// The lock is advisory: cargo itself does not check it.
+#[derive(Debug)]
+pub struct Lock {
+    file: File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another process holds the lock, with its pid if it is known.
+    Busy(Option<u32>),
+    /// The lock file could not be opened or locked.
+    Io(String),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Busy(Some(pid)) => write!(
+                f,
+                "another restore is running (pid {}), use --wait to wait for it",
+                pid
+            ),
+            Self::Busy(None) => write!(f, "another restore is running, use --wait to wait for it"),
+            Self::Io(e) => write!(f, "failed to lock the install root: {}", e),
+        }
+    }
+}
+
+impl Error for LockError {}
+
+/// Returns the lock file in the install root, next to `.crates2.json`.
+fn get_lock_path() -> PathBuf {
+    crate::get_cargo_home().join("cargo-backup.lock")
+}
+
+/// Locks the install root for this process.
+///
+/// # Errors
+/// * If another process holds the lock and `wait` is false.
+///   With `wait`, this blocks until the other process is done instead.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::lock;
+///
+/// let _lock = lock::acquire(true).unwrap();
+/// // install or remove packages
+/// ```
+pub fn acquire(wait: bool) -> Result<Lock, LockError> {
+    acquire_at(&get_lock_path(), wait)
+}
+
+fn acquire_at(path: &Path, wait: bool) -> Result<Lock, LockError> {
+    let io_error = |e: std::io::Error| LockError::Io(e.to_string());
+
+    create_dir_all(path.parent().unwrap()).map_err(io_error)?;
+    // Not truncated, the file contains the pid of the process holding the lock
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(io_error)?;
+
+    if !file.try_lock_exclusive().map_err(io_error)? {
+        let pid = read_pid(&mut file);
+        if !wait {
+            return Err(LockError::Busy(pid));
+        }
+
+        match pid {
+            Some(pid) => report::info(format!("Waiting for another restore (pid {})", pid)),
+            None => report::info("Waiting for another restore"),
+        }
+        file.lock_exclusive().map_err(io_error)?;
+    }
+
+    file.set_len(0).map_err(io_error)?;
+    file.rewind().map_err(io_error)?;
+    write!(file, "{}", std::process::id()).map_err(io_error)?;
+
+    Ok(Lock { file })
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+#[test]
+fn test_acquire() {
+    let path = std::env::temp_dir().join(format!("cargo-backup-{}.lock", std::process::id()));
+
+    let lock = acquire_at(&path, false).unwrap();
+    assert!(matches!(
+        acquire_at(&path, false),
+        Err(LockError::Busy(Some(pid))) if pid == std::process::id()
+    ));
+
+    drop(lock);
+    assert!(acquire_at(&path, false).is_ok());
+    let _ = std::fs::remove_file(path);
+}
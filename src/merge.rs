@@ -0,0 +1,172 @@
+use crate::{diff::same_features, Package};
+use std::{fmt, str::FromStr};
+
+/// How backups are combined.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Every package of every backup.
+    /// Conflicts are resolved by taking the highest version and combining the features.
+    #[default]
+    Union,
+    /// Only the packages found in every backup, resolved like [`Strategy::Union`].
+    Intersection,
+    /// Every package of every backup. Later backups replace the packages of earlier ones.
+    Override,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "union" => Ok(Self::Union),
+            "intersection" => Ok(Self::Intersection),
+            "override" => Ok(Self::Override),
+            _ => Err(format!("unknown merge strategy {:?}", s)),
+        }
+    }
+}
+
+/// A package found with different options in two backups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The package as it was merged so far.
+    pub previous: Package,
+    /// The package of the backup that was merged in.
+    pub next: Package,
+    /// The index of the backup `next` comes from.
+    pub backup: usize,
+    /// The package after resolving the conflict.
+    pub resolved: Package,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} [{}] and {} [{}] in backup {} resolved to {} [{}]",
+            self.resolved.name,
+            self.previous.version,
+            self.previous.features.join(","),
+            self.next.version,
+            self.next.features.join(","),
+            self.backup + 1,
+            self.resolved.version,
+            self.resolved.features.join(","),
+        )
+    }
+}
+
+/// Merges the backups in order.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::merge::{merge, Strategy};
+///
+/// let (packages, conflicts) = merge(&[team_baseline, personal], Strategy::Override);
+/// ```
+pub fn merge(backups: &[Vec<Package>], strategy: Strategy) -> (Vec<Package>, Vec<Conflict>) {
+    let mut merged: Vec<Package> = vec![];
+    let mut conflicts = vec![];
+
+    for (i, backup) in backups.iter().enumerate() {
+        for package in backup {
+            let Some(previous) = merged.iter_mut().find(|p| p.name == package.name) else {
+                merged.push(package.clone());
+                continue;
+            };
+
+            let resolved = match strategy {
+                Strategy::Override => package.clone(),
+                Strategy::Union | Strategy::Intersection => combine(previous, package),
+            };
+
+            if !same_options(previous, package) {
+                conflicts.push(Conflict {
+                    previous: previous.clone(),
+                    next: package.clone(),
+                    backup: i,
+                    resolved: resolved.clone(),
+                });
+            }
+
+            *previous = resolved;
+        }
+    }
+
+    if strategy == Strategy::Intersection {
+        merged.retain(|p| {
+            backups
+                .iter()
+                .all(|backup| backup.iter().any(|b| b.name == p.name))
+        });
+        conflicts.retain(|c| merged.iter().any(|p| p.name == c.resolved.name));
+    }
+
+    (merged, conflicts)
+}
+
+/// Checks if two packages are installed the same way.
+fn same_options(a: &Package, b: &Package) -> bool {
+    a.version == b.version && same_features(a, b)
+}
+
+/// Combines two entries of the same package,
+/// taking the highest version and the features and groups of both.
+fn combine(a: &Package, b: &Package) -> Package {
+    let mut package = if b.version > a.version {
+        b.clone()
+    } else {
+        a.clone()
+    };
+
+    for feature in a.features.iter().chain(&b.features) {
+        if !package.features.contains(feature) {
+            package.features.push(feature.clone());
+        }
+    }
+
+    for group in a.groups.iter().chain(&b.groups) {
+        if !package.groups.contains(group) {
+            package.groups.push(group.clone());
+        }
+    }
+
+    package.all_features = a.all_features || b.all_features;
+    package.no_default_features = a.no_default_features && b.no_default_features;
+
+    package
+}
+
+#[cfg(test)]
+use crate::platform::package;
+
+#[test]
+fn test_merge() {
+    let baseline = vec![
+        package("ripgrep", "14.0.0", &[]),
+        package("cargo-edit", "0.12.0", &["vendored-openssl"]),
+    ];
+    let personal = vec![
+        package("cargo-edit", "0.11.0", &["add"]),
+        package("bat", "0.24.0", &[]),
+    ];
+    let backups = [baseline, personal];
+
+    let (merged, conflicts) = merge(&backups, Strategy::Union);
+    assert_eq!(merged.len(), 3);
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(
+        conflicts[0].resolved,
+        package("cargo-edit", "0.12.0", &["vendored-openssl", "add"])
+    );
+
+    let (merged, _) = merge(&backups, Strategy::Intersection);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].name, "cargo-edit");
+
+    let (merged, conflicts) = merge(&backups, Strategy::Override);
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[1], package("cargo-edit", "0.11.0", &["add"]));
+    assert_eq!(conflicts[0].backup, 1);
+}
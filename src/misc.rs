@@ -1,18 +1,21 @@
 #![allow(dead_code)]
 
-use crate::Package;
-use owo_colors::OwoColorize;
-use std::{
-    fmt,
-    process::{Command, Stdio},
+use crate::{
+    color::{glyphs, paint, Stream},
+    diff::Diff,
+    plan::{describe_features, Plan, Step},
+    Package,
 };
+use owo_colors::Style;
+use std::fmt;
 
 pub(crate) enum CommandType {
     Remove,
     Install,
 }
 
-pub(crate) fn execute_cmd(package: &Package, cmd_type: CommandType) {
+/// Builds the cargo arguments to install or remove the package.
+pub(crate) fn build_args(package: &Package, cmd_type: &CommandType) -> Vec<String> {
     let mut args: Vec<String> = Vec::new();
 
     match cmd_type {
@@ -44,90 +47,191 @@ pub(crate) fn execute_cmd(package: &Package, cmd_type: CommandType) {
         }
     }
 
-    let mut child = Command::new("cargo")
-        .args(args)
-        .stdout(Stdio::piped())
-        .spawn()
-        .unwrap();
-
-    child.wait().unwrap();
+    args
 }
 
-pub(crate) fn pretty_print_packages(
-    to_install: &[Package],
-    to_update: &[Package],
-    to_remove: &[Package],
-) {
-    if !to_install.is_empty() {
-        println!("{}", "┌ Installing:".green().bold());
-        let mut to_install_iter = to_install.iter().peekable();
-
-        while let Some(package) = to_install_iter.next() {
-            if to_install_iter.peek().is_some() {
-                print!("{}", "├ ".green())
-            } else {
-                print!("{}", "└ ".green());
-            }
+/// Styles a package name.
+fn name(name: &str) -> String {
+    paint(name, Style::new().cyan().bold())
+}
 
-            print!(
-                "{} [{}]",
-                package.name.cyan().bold(),
-                package.version.to_string().green()
-            );
-            println!()
-        }
-        println!();
-    }
+pub(crate) fn pretty_print_plan(plan: &Plan) {
+    let change = |step: &Step| {
+        format!(
+            "{} {} -> {}",
+            name(&step.package.name),
+            paint(
+                step.installed
+                    .as_ref()
+                    .map(|p| p.version.to_string())
+                    .unwrap_or_default(),
+                Style::new().red().strikethrough()
+            ),
+            paint(&step.package.version, Style::new().green())
+        )
+    };
+
+    print_tree(
+        "Installing",
+        Style::new().green(),
+        plan.installs
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} [{}]",
+                    name(&s.package.name),
+                    paint(&s.package.version, Style::new().green())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Updating",
+        Style::new().yellow(),
+        plan.updates.iter().map(change).collect(),
+    );
+
+    print_tree(
+        "Downgrading",
+        Style::new().yellow(),
+        plan.downgrades.iter().map(change).collect(),
+    );
+
+    print_tree(
+        "Reconfiguring",
+        Style::new().magenta(),
+        plan.reconfigures
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} [{}] {}",
+                    name(&s.package.name),
+                    s.package.version,
+                    paint(&s.reason, Style::new().bright_black())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Removing",
+        Style::new().red(),
+        plan.removals
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} [{}]",
+                    name(&s.package.name),
+                    paint(&s.package.version, Style::new().red())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Skipping (filtered)",
+        Style::new().bright_black(),
+        plan.skipped
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} [{}]",
+                    name(&p.name),
+                    paint(&p.version, Style::new().bright_black())
+                )
+            })
+            .collect(),
+    );
+}
 
-    if !to_update.is_empty() {
-        println!("{}", "┌ Updating:".yellow().bold());
-        let mut to_update_iter = to_update.iter().peekable();
+pub(crate) fn pretty_print_diff(diff: &Diff) {
+    print_tree(
+        "Added",
+        Style::new().green(),
+        diff.added
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} [{}]",
+                    name(&p.name),
+                    paint(&p.version, Style::new().green())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Removed",
+        Style::new().red(),
+        diff.removed
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} [{}]",
+                    name(&p.name),
+                    paint(&p.version, Style::new().red())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Version changed",
+        Style::new().yellow(),
+        diff.version_changed
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} {} -> {}",
+                    name(&c.to.name),
+                    paint(&c.from.version, Style::new().red().strikethrough()),
+                    paint(&c.to.version, Style::new().green())
+                )
+            })
+            .collect(),
+    );
+
+    print_tree(
+        "Features changed",
+        Style::new().magenta(),
+        diff.features_changed
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} [{}] -> [{}]",
+                    name(&c.to.name),
+                    paint(describe_features(&c.from), Style::new().red()),
+                    paint(describe_features(&c.to), Style::new().green())
+                )
+            })
+            .collect(),
+    );
+}
 
-        while let Some(package) = to_update_iter.next() {
-            if to_update_iter.peek().is_some() {
-                print!("{}", "├ ".yellow())
-            } else {
-                print!("{}", "└ ".yellow());
-            }
-            print!(
-                "{} {} -> {}",
-                package.name.cyan().bold(),
-                to_update
-                    .iter()
-                    .find(|np| np.name == package.name)
-                    .unwrap()
-                    .version
-                    .to_string()
-                    .red()
-                    .strikethrough(),
-                package.version.to_string().green()
-            );
-            println!()
-        }
-        println!();
+fn print_tree(title: &str, style: Style, lines: Vec<String>) {
+    if lines.is_empty() {
+        return;
     }
 
-    if !to_remove.is_empty() {
-        println!("{}", "┌ Removing:".red().bold());
-
-        let mut to_remove_iter = to_remove.iter().peekable();
+    let glyphs = glyphs(Stream::Stdout);
 
-        while let Some(package) = to_remove_iter.next() {
-            if to_remove_iter.peek().is_some() {
-                print!("{}", "├ ".red())
-            } else {
-                print!("{}", "└ ".red());
-            }
-
-            print!(
-                "{} [{}]",
-                package.name.cyan().bold(),
-                package.version.to_string().red()
-            );
+    println!(
+        "{}",
+        paint(format!("{} {}:", glyphs.top, title), style.bold())
+    );
+    let mut lines_iter = lines.iter().peekable();
 
-            println!()
+    while let Some(line) = lines_iter.next() {
+        if lines_iter.peek().is_some() {
+            print!("{} ", paint(glyphs.branch, style))
+        } else {
+            print!("{} ", paint(glyphs.last, style));
         }
+
+        println!("{}", line);
     }
+    println!();
 }
 
 pub(crate) enum Errors {
@@ -0,0 +1,123 @@
+use crate::Package;
+use std::{error::Error, fmt};
+
+/// The packages depend on each other through `after`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CycleError {
+    /// The packages of the cycle, starting and ending with the same package,
+    /// where each package is installed after the next one.
+    pub cycle: Vec<String>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the packages must be installed after each other: {}",
+            self.cycle.join(" -> ")
+        )
+    }
+}
+
+impl Error for CycleError {}
+
+/// Sorts the packages so each one comes after the packages in its `after` field.
+/// Among the packages that can go next, the one with the highest `priority` goes first,
+/// and packages with the same priority keep their order.
+/// Names in `after` that are not in the list are ignored.
+///
+/// Returns the indices of the packages in the sorted order.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::order::sort;
+///
+/// let order = sort(&backup.iter().collect::<Vec<_>>()).unwrap();
+/// for i in order {
+///     println!("{}", backup[i].name);
+/// }
+/// ```
+pub fn sort(packages: &[&Package]) -> Result<Vec<usize>, CycleError> {
+    // The indices of the packages each package has to wait for
+    let dependencies: Vec<Vec<usize>> = packages
+        .iter()
+        .map(|package| {
+            packages
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| package.after.contains(&other.name))
+                .map(|(j, _)| j)
+                .collect()
+        })
+        .collect();
+
+    let mut waiting: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut done = vec![false; packages.len()];
+    let mut order = vec![];
+
+    while let Some(next) = (0..packages.len())
+        .filter(|&i| !done[i] && waiting[i] == 0)
+        .min_by_key(|&i| (-packages[i].priority, i))
+    {
+        done[next] = true;
+        order.push(next);
+
+        for (i, dependencies) in dependencies.iter().enumerate() {
+            if dependencies.contains(&next) {
+                waiting[i] -= 1;
+            }
+        }
+    }
+
+    if order.len() == packages.len() {
+        return Ok(order);
+    }
+
+    // Every package left waits for another package left, so following them leads into a cycle
+    let mut path = vec![done.iter().position(|done| !done).unwrap()];
+    loop {
+        let current = *path.last().unwrap();
+        let next = *dependencies[current].iter().find(|&&j| !done[j]).unwrap();
+
+        if let Some(start) = path.iter().position(|&i| i == next) {
+            let mut cycle: Vec<String> = path[start..]
+                .iter()
+                .map(|&i| packages[i].name.clone())
+                .collect();
+            cycle.push(packages[next].name.clone());
+            return Err(CycleError { cycle });
+        }
+        path.push(next);
+    }
+}
+
+#[test]
+fn test_sort() {
+    let package = |name: &str, priority: i32, after: &[&str]| Package {
+        priority,
+        after: after.iter().map(|a| a.to_string()).collect(),
+        ..crate::platform::package(name, "1.0.0", &[])
+    };
+
+    let packages = [
+        package("my-tool", 0, &["codegen", "not-in-the-list"]),
+        package("ripgrep", 0, &[]),
+        package("codegen", 0, &[]),
+        package("cargo-binstall", 10, &[]),
+    ];
+    let order = sort(&packages.iter().collect::<Vec<_>>()).unwrap();
+    assert_eq!(order, vec![3, 1, 2, 0]);
+
+    let packages = [
+        package("ripgrep", 0, &[]),
+        package("a", 0, &["c"]),
+        package("b", 0, &["a"]),
+        package("c", 0, &["b"]),
+    ];
+    assert_eq!(
+        sort(&packages.iter().collect::<Vec<_>>())
+            .unwrap_err()
+            .cycle,
+        vec!["a", "c", "b", "a"]
+    );
+}
@@ -0,0 +1,446 @@
+use crate::{
+    apply_previous,
+    diff::same_features,
+    filter::Filter,
+    install,
+    misc::{build_args, CommandType},
+    order::{self, CycleError},
+    platform::{self, Host},
+    report, Package,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Install,
+    Update,
+    Downgrade,
+    Reconfigure,
+    Remove,
+}
+
+/// A single step of a restore and the cargo command that runs it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub action: Action,
+    /// The package as it should be installed, or the package to remove.
+    pub package: Package,
+    /// The installed package that is changed by the step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<Package>,
+    /// Why the step is needed, e.g. `installed 0.5.2 is older than 0.5.3`.
+    pub reason: String,
+    pub command: Vec<String>,
+    /// The environment variables set for the command.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Step {
+    /// Creates the step, adding the toolchain, arguments and environment of the options and the package to installs.
+    fn new(
+        action: Action,
+        package: Package,
+        installed: Option<Package>,
+        reason: String,
+        options: &PlanOptions,
+    ) -> Self {
+        let cargo = options.cargo.clone().unwrap_or_else(|| "cargo".to_string());
+        let mut command = vec![];
+        let mut env = BTreeMap::new();
+
+        if action == Action::Remove {
+            command.push(cargo);
+            command.extend(build_args(&package, &CommandType::Remove));
+        } else {
+            match &package.toolchain {
+                // Only the rustup proxy understands `+toolchain`, `CARGO` usually points to a toolchain's cargo
+                Some(toolchain) => command.extend(["cargo".to_string(), format!("+{}", toolchain)]),
+                None => command.push(cargo),
+            }
+            command.extend(build_args(&package, &CommandType::Install));
+            command.extend(options.install.args.iter().chain(&package.args).cloned());
+            env.extend(options.install.env.clone());
+            env.extend(package.env.clone());
+        }
+
+        Self {
+            action,
+            package,
+            installed,
+            reason,
+            command,
+            env,
+        }
+    }
+}
+
+/// Options for [`plan`].
+#[derive(Debug, Default, Clone)]
+pub struct PlanOptions {
+    /// Skip the installation of packages not installed yet.
+    pub skip_install: bool,
+    /// Skip the update, downgrade and reconfiguration of installed packages.
+    pub skip_update: bool,
+    /// Skip the removal of packages not found in the backup.
+    pub skip_remove: bool,
+    /// Only packages selected by the filter are installed, updated or removed.
+    pub filter: Filter,
+    /// The arguments and environment added to every install.
+    pub install: install::Config,
+    /// The cargo binary to run, e.g. from the `CARGO` environment variable. Defaults to `cargo`.
+    pub cargo: Option<String>,
+}
+
+/// The steps needed to make the installed packages match a backup.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub installs: Vec<Step>,
+    pub updates: Vec<Step>,
+    pub downgrades: Vec<Step>,
+    /// Installed in the right version, but with different features.
+    pub reconfigures: Vec<Step>,
+    pub removals: Vec<Step>,
+    /// Packages left untouched because of the filter or their platform conditions.
+    pub skipped: Vec<Package>,
+    /// The packages of the installs, updates, downgrades and reconfigures in the order they are executed,
+    /// set by [`Plan::order`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+}
+
+impl Plan {
+    /// Returns all steps in the order they are executed, removals last.
+    pub fn steps(&self) -> impl Iterator<Item = &Step> {
+        let mut changes: Vec<&Step> = self
+            .installs
+            .iter()
+            .chain(&self.updates)
+            .chain(&self.downgrades)
+            .chain(&self.reconfigures)
+            .collect();
+
+        if !self.order.is_empty() {
+            changes.sort_by_key(|step| {
+                self.order
+                    .iter()
+                    .position(|name| *name == step.package.name)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        changes.into_iter().chain(&self.removals)
+    }
+
+    /// Orders the steps by the `priority` and `after` fields of their packages,
+    /// see [`order::sort`]. Removals are not ordered and stay last.
+    ///
+    /// # Errors
+    /// * If the packages depend on each other in a cycle.
+    pub fn order(&mut self) -> Result<(), CycleError> {
+        self.order.clear();
+
+        let packages: Vec<&Package> = self
+            .steps()
+            .filter(|step| step.action != Action::Remove)
+            .map(|step| &step.package)
+            .collect();
+
+        self.order = order::sort(&packages)?
+            .into_iter()
+            .map(|i| packages[i].name.clone())
+            .collect();
+        Ok(())
+    }
+
+    /// Returns true if nothing needs to be done.
+    pub fn is_empty(&self) -> bool {
+        self.steps().next().is_none()
+    }
+
+    /// Only keeps the steps for which the predicate returns true.
+    pub fn retain(&mut self, mut f: impl FnMut(&Step) -> bool) {
+        self.installs.retain(&mut f);
+        self.updates.retain(&mut f);
+        self.downgrades.retain(&mut f);
+        self.reconfigures.retain(&mut f);
+        self.removals.retain(&mut f);
+    }
+
+    /// Reports the plan, printed as a colored tree by the [`report::HumanReporter`].
+    pub fn print(&self) {
+        report::event(&report::Event::Plan {
+            plan: self,
+            dry_run: false,
+            confirm: false,
+        });
+    }
+}
+
+/// Computes the steps to get from the installed to the desired packages.
+///
+/// Nothing is read from or written to the system, so plans can be inspected,
+/// filtered and serialized before they are applied.
+/// Installed packages inherit the groups of the desired package with the same name,
+/// and are skipped instead of removed if the desired package is meant for another platform.
+/// Overrides only change the desired features, the installed features are compared as they are.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{get_packages, plan::{plan, PlanOptions}, platform::Host};
+///
+/// let plan = plan(&get_packages(), &backup, &PlanOptions::default(), &Host::current());
+/// for step in plan.steps() {
+///     println!("{}: {}", step.package.name, step.reason);
+/// }
+/// ```
+pub fn plan(
+    installed: &[Package],
+    desired: &[Package],
+    options: &PlanOptions,
+    host: &Host,
+) -> Plan {
+    let mut installed = installed.to_vec();
+    apply_previous(&mut installed, desired);
+    for package in &mut installed {
+        package.only_on = None;
+        package.overrides.clear();
+    }
+
+    let (desired, other_platform) = platform::resolve(desired.to_vec(), host);
+    let (installed_other_platform, installed): (Vec<Package>, Vec<Package>) = installed
+        .into_iter()
+        .partition(|package| other_platform.iter().any(|p| p.name == package.name));
+
+    let (installed, installed_skipped) = options.filter.partition(installed);
+    let (desired, skipped) = options.filter.partition(desired);
+
+    let mut plan = Plan {
+        skipped,
+        ..Default::default()
+    };
+
+    for package in installed_skipped
+        .into_iter()
+        .chain(other_platform)
+        .chain(installed_other_platform)
+    {
+        if !plan.skipped.iter().any(|p| p.name == package.name) {
+            plan.skipped.push(package);
+        }
+    }
+
+    for package in &desired {
+        let Some(current) = installed.iter().find(|p| p.name == package.name) else {
+            if !options.skip_install {
+                plan.installs.push(Step::new(
+                    Action::Install,
+                    package.clone(),
+                    None,
+                    String::from("not installed"),
+                    options,
+                ));
+            }
+            continue;
+        };
+
+        if options.skip_update {
+            continue;
+        }
+
+        let (action, reason) = if package.version > current.version {
+            (
+                Action::Update,
+                format!(
+                    "installed {} is older than {}",
+                    current.version, package.version
+                ),
+            )
+        } else if package.version < current.version {
+            (
+                Action::Downgrade,
+                format!(
+                    "installed {} is newer than {}",
+                    current.version, package.version
+                ),
+            )
+        } else if !same_features(current, package) {
+            (
+                Action::Reconfigure,
+                format!(
+                    "features changed from [{}] to [{}]",
+                    describe_features(current),
+                    describe_features(package)
+                ),
+            )
+        } else {
+            continue;
+        };
+
+        let step = Step::new(
+            action,
+            package.clone(),
+            Some(current.clone()),
+            reason,
+            options,
+        );
+        match action {
+            Action::Update => plan.updates.push(step),
+            Action::Downgrade => plan.downgrades.push(step),
+            _ => plan.reconfigures.push(step),
+        }
+    }
+
+    if !options.skip_remove {
+        for package in &installed {
+            if !desired.iter().any(|p| p.name == package.name) {
+                plan.removals.push(Step::new(
+                    Action::Remove,
+                    package.clone(),
+                    Some(package.clone()),
+                    String::from("not in the backup"),
+                    options,
+                ));
+            }
+        }
+    }
+
+    plan
+}
+
+/// Describes the features and feature flags of the package, e.g. `add, --no-default-features`.
+pub(crate) fn describe_features(package: &Package) -> String {
+    let mut flags = package.features.clone();
+    if package.all_features {
+        flags.push("--all-features".to_string());
+    }
+    if package.no_default_features {
+        flags.push("--no-default-features".to_string());
+    }
+    flags.join(", ")
+}
+
+#[test]
+fn test_plan() {
+    use platform::package;
+
+    let host = platform::linux_host();
+
+    let installed = vec![
+        package("ripgrep", "13.0.0", &[]),
+        package("bat", "0.24.0", &[]),
+        package("cargo-edit", "0.12.0", &["add"]),
+        package("fd-find", "9.0.0", &[]),
+        package("trunk", "0.18.0", &[]),
+    ];
+    let desired = vec![
+        package("ripgrep", "14.0.0", &[]),
+        package("bat", "0.23.0", &[]),
+        package("cargo-edit", "0.12.0", &["add", "rm"]),
+        package("fd-find", "9.0.0", &[]),
+        package("cargo-nextest", "0.9.66", &[]),
+    ];
+
+    let result = plan(&installed, &desired, &PlanOptions::default(), &host);
+    assert_eq!(result.installs[0].package.name, "cargo-nextest");
+    assert_eq!(result.installs[0].reason, "not installed");
+    assert_eq!(
+        result.updates[0].installed.as_ref().unwrap().version.major,
+        13
+    );
+    assert_eq!(result.downgrades[0].package.name, "bat");
+    assert_eq!(
+        result.reconfigures[0].reason,
+        "features changed from [add] to [add, rm]"
+    );
+    assert_eq!(result.removals[0].package.name, "trunk");
+    assert_eq!(result.steps().count(), 5);
+
+    let options = PlanOptions {
+        skip_update: true,
+        filter: Filter::new(vec![], vec!["trunk".to_string()]),
+        ..Default::default()
+    };
+    let result = plan(&installed, &desired, &options, &host);
+    assert_eq!(result.steps().count(), 1);
+    assert_eq!(result.skipped[0].name, "trunk");
+
+    // The override only applies to the backup, the installed features are the real ones
+    let vendored = Package {
+        overrides: vec![platform::Override {
+            when: "cfg(target_os = \"linux\")".to_string(),
+            features: Some(vec!["vendored".to_string()]),
+            all_features: None,
+            no_default_features: None,
+        }],
+        ..package("cargo-edit", "0.12.0", &["default-tls"])
+    };
+    let result = plan(
+        &[package("cargo-edit", "0.12.0", &["default-tls"])],
+        &[vendored],
+        &PlanOptions::default(),
+        &host,
+    );
+    assert_eq!(
+        result.reconfigures[0].reason,
+        "features changed from [default-tls] to [vendored]"
+    );
+    assert_eq!(result.steps().count(), 1);
+
+    // Installed packages meant for another platform are neither reconfigured nor removed
+    let windows = Package {
+        only_on: Some("cfg(windows)".to_string()),
+        ..package("cargo-edit", "0.12.0", &["vendored"])
+    };
+    let result = plan(
+        &[package("cargo-edit", "0.12.0", &["default-tls"])],
+        &[windows],
+        &PlanOptions::default(),
+        &host,
+    );
+    assert!(result.is_empty());
+    assert_eq!(result.skipped[0].name, "cargo-edit");
+
+    let nightly = Package {
+        toolchain: Some("nightly".to_string()),
+        ..package("cargo-udeps", "0.1.45", &[])
+    };
+    let options = PlanOptions {
+        cargo: Some("/opt/cargo".to_string()),
+        ..Default::default()
+    };
+    let result = plan(&installed, &[nightly], &options, &host);
+    assert_eq!(
+        result.installs[0].command[..3],
+        ["cargo", "+nightly", "install"]
+    );
+    assert_eq!(result.removals[0].command[0], "/opt/cargo");
+
+    let desired = vec![
+        Package {
+            after: vec!["bat".to_string()],
+            ..package("ripgrep", "14.0.0", &[])
+        },
+        package("cargo-nextest", "0.9.66", &[]),
+        package("bat", "0.23.0", &[]),
+    ];
+    let mut result = plan(&installed, &desired, &PlanOptions::default(), &host);
+    result.order().unwrap();
+    let steps: Vec<&str> = result
+        .steps()
+        .map(|step| step.package.name.as_str())
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            "cargo-nextest",
+            "bat",
+            "ripgrep",
+            "cargo-edit",
+            "fd-find",
+            "trunk"
+        ]
+    );
+}
@@ -0,0 +1,368 @@
+use crate::{filter::glob_match, report, Package};
+use serde::{Deserialize, Serialize};
+use std::{env::consts, error::Error, fmt, process::Command};
+
+/// Overrides the install options of a package on matching platforms.
+///
+/// # Examples
+/// ```json
+/// { "when": "cfg(windows)", "features": ["vendored-openssl"] }
+/// ```
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct Override {
+    /// A `cfg(...)` expression or a target triple pattern like `*-linux-*`.
+    pub when: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_features: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_default_features: Option<bool>,
+}
+
+/// The platform the packages are installed on.
+#[derive(Debug, Clone)]
+pub struct Host {
+    pub triple: String,
+    pub arch: String,
+    pub os: String,
+    pub family: String,
+    pub env: String,
+    pub pointer_width: String,
+    pub endian: String,
+}
+
+impl Host {
+    /// Detects the current host. The target triple is read from `rustc -vV`.
+    pub fn current() -> Self {
+        let triple = Command::new("rustc")
+            .arg("-vV")
+            .output()
+            .ok()
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.strip_prefix("host: ").map(String::from))
+            })
+            .unwrap_or_else(|| format!("{}-unknown-{}", consts::ARCH, consts::OS));
+
+        let env = if cfg!(target_env = "gnu") {
+            "gnu"
+        } else if cfg!(target_env = "musl") {
+            "musl"
+        } else if cfg!(target_env = "msvc") {
+            "msvc"
+        } else {
+            ""
+        };
+
+        Self {
+            triple,
+            arch: consts::ARCH.to_string(),
+            os: consts::OS.to_string(),
+            family: consts::FAMILY.to_string(),
+            env: env.to_string(),
+            pointer_width: (usize::BITS).to_string(),
+            endian: if cfg!(target_endian = "little") {
+                "little".to_string()
+            } else {
+                "big".to_string()
+            },
+        }
+    }
+
+    /// Looks up the value of a `cfg` key, e.g. `target_os`.
+    fn cfg_value(&self, key: &str) -> Option<&str> {
+        match key {
+            "target_arch" => Some(&self.arch),
+            "target_os" => Some(&self.os),
+            "target_family" => Some(&self.family),
+            "target_env" => Some(&self.env),
+            "target_pointer_width" => Some(&self.pointer_width),
+            "target_endian" => Some(&self.endian),
+            "target" => Some(&self.triple),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConditionError {
+    pub condition: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid condition {:?}: {}", self.condition, self.reason)
+    }
+}
+
+impl Error for ConditionError {}
+
+/// Evaluates a condition against the host.
+///
+/// The condition is either a `cfg(...)` expression like `cfg(all(unix, not(target_arch = "arm")))`
+/// or a target triple pattern like `x86_64-*-linux-gnu`.
+///
+/// # Examples
+/// ```no_run
+/// let host = Host::current();
+/// assert!(matches("cfg(any(unix, windows))", &host).unwrap());
+/// ```
+pub fn matches(condition: &str, host: &Host) -> Result<bool, ConditionError> {
+    let condition = condition.trim();
+
+    let error = |reason: String| ConditionError {
+        condition: condition.to_string(),
+        reason,
+    };
+
+    let Some(expr) = condition.strip_prefix("cfg(") else {
+        return Ok(glob_match(condition, &host.triple));
+    };
+    let expr = expr
+        .strip_suffix(')')
+        .ok_or_else(|| error("missing closing parenthesis".to_string()))?;
+
+    let mut parser = Parser {
+        tokens: tokenize(expr).map_err(error)?,
+        pos: 0,
+    };
+    let result = parser.expr(host).map_err(error)?;
+
+    match parser.tokens.get(parser.pos) {
+        None => Ok(result),
+        Some(token) => Err(error(format!("unexpected {:?}", token))),
+    }
+}
+
+/// Applies the platform conditions of the packages.
+///
+/// Returns the packages for the host with their overrides applied,
+/// and the packages that are not meant for the host.
+pub fn resolve(packages: Vec<Package>, host: &Host) -> (Vec<Package>, Vec<Package>) {
+    let is_match = |condition: &str| {
+        matches(condition, host).unwrap_or_else(|e| {
+            report::warn(e);
+            false
+        })
+    };
+
+    let mut selected = vec![];
+    let mut skipped = vec![];
+
+    for mut package in packages {
+        if !package.only_on.as_deref().is_none_or(is_match) {
+            skipped.push(package);
+            continue;
+        }
+
+        for o in package.overrides.clone() {
+            if !is_match(&o.when) {
+                continue;
+            }
+
+            if let Some(features) = o.features {
+                package.features = features;
+            }
+            if let Some(all_features) = o.all_features {
+                package.all_features = all_features;
+            }
+            if let Some(no_default_features) = o.no_default_features {
+                package.no_default_features = no_default_features;
+            }
+        }
+
+        selected.push(package);
+    }
+
+    (selected, skipped)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Eq,
+    Comma,
+    Open,
+    Close,
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = expr.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '=' => tokens.push(Token::Eq),
+            ',' => tokens.push(Token::Comma),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => value.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => return Err(format!("unexpected character {:?}", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {:?}, found {:?}", expected, token)),
+            None => Err(format!("expected {:?}", expected)),
+        }
+    }
+
+    fn expr(&mut self, host: &Host) -> Result<bool, String> {
+        let ident = match self.next() {
+            Some(Token::Ident(ident)) => ident,
+            Some(token) => return Err(format!("unexpected {:?}", token)),
+            None => return Err("unexpected end of expression".to_string()),
+        };
+
+        match (ident.as_str(), self.tokens.get(self.pos)) {
+            ("all" | "any", Some(Token::Open)) => {
+                self.pos += 1;
+                let mut results = vec![];
+                while self.tokens.get(self.pos) != Some(&Token::Close) {
+                    results.push(self.expr(host)?);
+                    if self.tokens.get(self.pos) == Some(&Token::Comma) {
+                        self.pos += 1;
+                    }
+                }
+                self.expect(Token::Close)?;
+
+                if ident == "all" {
+                    Ok(results.iter().all(|r| *r))
+                } else {
+                    Ok(results.iter().any(|r| *r))
+                }
+            }
+            ("not", Some(Token::Open)) => {
+                self.pos += 1;
+                let result = self.expr(host)?;
+                self.expect(Token::Close)?;
+                Ok(!result)
+            }
+            (key, Some(Token::Eq)) => {
+                self.pos += 1;
+                let value = match self.next() {
+                    Some(Token::Str(value)) => value,
+                    _ => return Err(format!("expected a string after {} =", key)),
+                };
+
+                match host.cfg_value(key) {
+                    Some(actual) => Ok(actual == value),
+                    None => Err(format!("unknown cfg key {:?}", key)),
+                }
+            }
+            ("unix" | "windows", _) => Ok(host.family == ident),
+            (name, _) => Err(format!("unknown cfg {:?}", name)),
+        }
+    }
+}
+
+/// Creates a package with the features, the fixture of the tests.
+#[cfg(test)]
+pub(crate) fn package(name: &str, version: &str, features: &[&str]) -> Package {
+    Package {
+        features: features.iter().map(|f| f.to_string()).collect(),
+        ..Package::new(name, semver::Version::parse(version).unwrap())
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn linux_host() -> Host {
+    Host {
+        triple: "x86_64-unknown-linux-gnu".to_string(),
+        arch: "x86_64".to_string(),
+        os: "linux".to_string(),
+        family: "unix".to_string(),
+        env: "gnu".to_string(),
+        pointer_width: "64".to_string(),
+        endian: "little".to_string(),
+    }
+}
+
+#[test]
+fn test_matches() {
+    let host = linux_host();
+
+    assert!(matches("x86_64-unknown-linux-gnu", &host).unwrap());
+    assert!(matches("*-linux-*", &host).unwrap());
+    assert!(!matches("*-windows-*", &host).unwrap());
+    assert!(matches("cfg(unix)", &host).unwrap());
+    assert!(!matches("cfg(windows)", &host).unwrap());
+    assert!(matches(r#"cfg(target_os = "linux")"#, &host).unwrap());
+    assert!(matches(
+        r#"cfg(all(unix, not(target_arch = "aarch64"), any(target_env = "gnu", target_env = "musl")))"#,
+        &host
+    )
+    .unwrap());
+    assert!(matches("cfg(foo)", &host).is_err());
+    assert!(matches(r#"cfg(target_os = "linux""#, &host).is_err());
+}
+
+#[test]
+fn test_resolve() {
+    let package = |name: &str, only_on: Option<&str>, overrides: Vec<Override>| Package {
+        only_on: only_on.map(String::from),
+        overrides,
+        ..package(name, "1.0.0", &["default-tls"])
+    };
+
+    let (selected, skipped) = resolve(
+        vec![
+            package("desktop-tool", Some("cfg(windows)"), vec![]),
+            package(
+                "server-tool",
+                Some("cfg(unix)"),
+                vec![Override {
+                    when: r#"cfg(target_os = "linux")"#.to_string(),
+                    features: Some(vec!["vendored".to_string()]),
+                    all_features: None,
+                    no_default_features: Some(true),
+                }],
+            ),
+        ],
+        &linux_host(),
+    );
+
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0].name, "desktop-tool");
+    assert_eq!(selected[0].features, vec!["vendored".to_string()]);
+    assert!(selected[0].no_default_features);
+}
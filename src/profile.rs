@@ -0,0 +1,118 @@
+use std::{error::Error, fmt, fs::read_dir, path::PathBuf};
+
+/// A named backup set, e.g. `work` or `minimal-ci`.
+///
+/// Each profile is stored in its own file, both locally and in the remote backup.
+/// The default profile uses the file name `backup.json`.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::profile::Profile;
+///
+/// let profile = Profile::new(Some("work")).unwrap();
+/// assert_eq!(profile.file_name(), "backup-work.json");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct InvalidProfileName(pub String);
+
+impl fmt::Display for InvalidProfileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid profile name {:?}, only letters, digits, '-' and '_' are allowed",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidProfileName {}
+
+impl Profile {
+    pub fn new(name: Option<&str>) -> Result<Self, InvalidProfileName> {
+        match name {
+            Some(name)
+                if name.is_empty()
+                    || !name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                Err(InvalidProfileName(name.to_string()))
+            }
+            Some(name) => Ok(Self {
+                name: Some(name.to_string()),
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Returns the name of the profile, or `None` for the default profile.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the file name of the backup, e.g. `backup-work.json`.
+    pub fn file_name(&self) -> String {
+        match &self.name {
+            Some(name) => format!("backup-{}.json", name),
+            None => String::from("backup.json"),
+        }
+    }
+
+    /// Returns the path where the backup of the profile is stored locally.
+    pub fn path(&self) -> PathBuf {
+        get_profiles_dir().join(self.file_name())
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name.as_deref().unwrap_or("default"))
+    }
+}
+
+/// Returns the directory containing the local profile backups.
+fn get_profiles_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap()
+        .join("cargo-backup")
+        .join("profiles")
+}
+
+/// Lists the profiles that have a local backup.
+pub fn list() -> Vec<Profile> {
+    let Ok(entries) = read_dir(get_profiles_dir()) else {
+        return vec![];
+    };
+
+    let mut profiles: Vec<Profile> = entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            match file_name.as_str() {
+                "backup.json" => Some(Profile::default()),
+                _ => Profile::new(Some(
+                    file_name.strip_prefix("backup-")?.strip_suffix(".json")?,
+                ))
+                .ok(),
+            }
+        })
+        .collect();
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+#[test]
+fn test_profile() {
+    assert_eq!(Profile::default().file_name(), "backup.json");
+    assert_eq!(
+        Profile::new(Some("minimal-ci")).unwrap().file_name(),
+        "backup-minimal-ci.json"
+    );
+    assert!(Profile::new(Some("../work")).is_err());
+    assert!(Profile::new(Some("")).is_err());
+}
@@ -0,0 +1,65 @@
+use crate::{
+    history::Estimates,
+    plan::Step,
+    report::{self, Event},
+};
+
+/// Reports which step of a restore is running and how long the rest will take.
+pub(crate) struct Progress {
+    /// The estimated duration of each step in execution order, if there is one.
+    estimates: Vec<Option<u64>>,
+    /// The number of steps that were started.
+    started: usize,
+}
+
+impl Progress {
+    pub fn new<'a>(steps: impl IntoIterator<Item = &'a Step>, estimates: &Estimates) -> Self {
+        Self {
+            estimates: steps
+                .into_iter()
+                .map(|step| estimates.get(&step.package.name, step.action))
+                .collect(),
+            started: 0,
+        }
+    }
+
+    /// Reports the start of the next step.
+    pub fn start(&mut self, step: &Step) {
+        let remaining_ms = self.remaining_ms();
+        self.started += 1;
+
+        report::event(&Event::Progress {
+            current: self.started,
+            total: self.estimates.len().max(self.started),
+            step,
+            remaining_ms,
+        });
+    }
+
+    /// Returns the estimated time of the steps that were not started yet.
+    /// Steps without an estimate are not counted, unless no step has one.
+    fn remaining_ms(&self) -> Option<u64> {
+        let remaining = self.estimates.get(self.started..).unwrap_or_default();
+
+        if remaining.iter().all(Option::is_none) {
+            None
+        } else {
+            Some(remaining.iter().flatten().sum())
+        }
+    }
+}
+
+#[test]
+fn test_remaining_ms() {
+    let mut progress = Progress {
+        estimates: vec![Some(30_000), None, Some(100)],
+        started: 0,
+    };
+    assert_eq!(progress.remaining_ms(), Some(30_100));
+
+    progress.started = 1;
+    assert_eq!(progress.remaining_ms(), Some(100));
+
+    progress.started = 3;
+    assert_eq!(progress.remaining_ms(), None);
+}
@@ -1,8 +1,7 @@
 use std::{collections::HashMap, thread, time::Duration};
 
 use super::{get_config, save_config, ProviderConfig, RemoteProvider};
-use crate::{url::UrlBuilder, Package};
-use owo_colors::OwoColorize;
+use crate::{apply_previous, profile::Profile, report, schema, sign, url::UrlBuilder, Package};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
@@ -29,31 +28,29 @@ impl Github {
         }
         None
     }
-}
-
-impl RemoteProvider for Github {
-    fn get_keyring() -> keyring::Entry {
-        keyring::Entry::new("cargo-backup", "github").expect("Could not open keyring")
-    }
 
-    fn new() -> Self {
-        Self {
-            keyring: Self::get_keyring(),
-            config: get_config(),
+    /// Fetches the backup of the profile from the gist.
+    /// Returns `None` if there is no gist yet or it has no backup for the profile.
+    fn fetch(
+        &self,
+        profile: &Profile,
+        verify: bool,
+    ) -> Result<Option<Vec<Package>>, Box<dyn std::error::Error>> {
+        match self.get_gist()? {
+            Some(gist) => read_backup(&gist, profile, verify),
+            None => Ok(None),
         }
     }
 
-    fn pull(&self) -> Result<Vec<crate::Package>, Box<dyn std::error::Error>> {
+    /// Fetches the gist, or returns `None` if there is no gist yet.
+    fn get_gist(&self) -> Result<Option<Gist>, Box<dyn std::error::Error>> {
         let auth = self
             .get_auth()
             .unwrap_or_else(|| panic!("Please login first with \"cargo sync login\""));
 
-        let gist_id: String = self
-            .config
-            .gist_id
-            .as_ref()
-            .unwrap_or_else(|| panic!("Gist Id not set"))
-            .to_owned();
+        let Some(gist_id) = self.config.gist_id.as_ref() else {
+            return Ok(None);
+        };
 
         let response: Gist = ureq::get(
             &UrlBuilder::new(&format!("https://api.github.com/gists/{}", gist_id)).build(),
@@ -69,20 +66,100 @@ impl RemoteProvider for Github {
         .call()?
         .into_json()?;
 
-        let mut packages: Vec<Package> = Vec::new();
-        for file in response.files.values() {
-            if file.filename == "backup.json" {
-                packages = serde_json::from_str(file.content.as_ref().unwrap())?;
-            }
+        Ok(Some(response))
+    }
+}
+
+/// Reads and verifies the backup of the profile from the gist.
+/// Returns `None` if the gist has no backup for the profile.
+fn read_backup(
+    gist: &Gist,
+    profile: &Profile,
+    verify: bool,
+) -> Result<Option<Vec<Package>>, Box<dyn std::error::Error>> {
+    let file_name = profile.file_name();
+
+    let Some(content) = gist
+        .files
+        .get(&file_name)
+        .and_then(|file| file.content.clone())
+    else {
+        return Ok(None);
+    };
+
+    let signature = gist
+        .files
+        .get(&format!("{}.sig", file_name))
+        .and_then(|file| file.content.as_deref());
+
+    match sign::check(content.as_bytes(), signature) {
+        Ok(Some(principal)) => report::info(format!("Backup signed by {}", principal)),
+        Ok(None) => {}
+        Err(e) if !verify => report::warn(e),
+        Err(e) => return Err(e.into()),
+    }
+
+    let packages: Vec<Package> = schema::parse(&content)?;
+    Ok(Some(packages))
+}
+
+impl RemoteProvider for Github {
+    fn get_keyring() -> keyring::Entry {
+        keyring::Entry::new("cargo-backup", "github").expect("Could not open keyring")
+    }
+
+    fn new() -> Self {
+        Self {
+            keyring: Self::get_keyring(),
+            config: get_config(),
         }
-        Ok(packages)
     }
 
-    fn push(&self, backup: &[crate::Package]) -> Result<(), Box<dyn std::error::Error>> {
+    fn pull(
+        &self,
+        profile: &Profile,
+        verify: bool,
+    ) -> Result<Vec<crate::Package>, Box<dyn std::error::Error>> {
+        if self.config.gist_id.is_none() {
+            panic!("Gist Id not set");
+        }
+
+        self.fetch(profile, verify)?
+            .ok_or_else(|| format!("The gist has no backup for the profile {}", profile).into())
+    }
+
+    fn push(
+        &self,
+        backup: &[crate::Package],
+        profile: &Profile,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let auth = self
             .get_auth()
             .unwrap_or_else(|| panic!("Please login first with \"cargo sync login\""));
 
+        let file_name = profile.file_name();
+        let signature_name = format!("{}.sig", file_name);
+
+        let gist = self.get_gist().unwrap_or_else(|e| {
+            report::warn(format!("Failed to fetch the gist: {}", e));
+            None
+        });
+        let signed = gist
+            .as_ref()
+            .is_some_and(|gist| gist.files.contains_key(&signature_name));
+
+        // Keep the groups, platform conditions, install options and ordering of the pushed backup.
+        // They are only copied from a verified backup, because the new one is signed again.
+        let mut backup = backup.to_vec();
+        match gist.map_or(Ok(None), |gist| read_backup(&gist, profile, true)) {
+            Ok(Some(previous)) => apply_previous(&mut backup, &previous),
+            Ok(None) => {}
+            Err(e) => report::warn(format!(
+                "Not keeping the groups and options of the pushed backup: {}",
+                e
+            )),
+        }
+
         let gist_id = self.config.gist_id.as_ref();
 
         let request = match gist_id {
@@ -90,6 +167,27 @@ impl RemoteProvider for Github {
             None => ureq::post("https://api.github.com/gists"),
         };
 
+        let content = serde_json::to_string(&backup).unwrap();
+
+        let mut files = json!({
+            &file_name: {
+                "content": content
+            }
+        });
+
+        if let Some(key) = sign::Config::load().key {
+            files[&signature_name] = json!({
+                "content": sign::sign(content.as_bytes(), &key)?
+            });
+        } else if signed {
+            // The old signature does not match the new backup and would fail every verified pull
+            report::warn(format!(
+                "Removing {} from the gist because no signing key is configured",
+                signature_name
+            ));
+            files[&signature_name] = serde_json::Value::Null;
+        }
+
         let result = request
             .set("Accept", "application/json")
             .set("Authorization", &format!("token {}", auth))
@@ -101,17 +199,24 @@ impl RemoteProvider for Github {
             .send_json(json!({
                 "description": "Cargo Package Backup (Created by cargo-backup https://github.com/Kiramily/cargo-backup)",
                 "public": false,
-                "files": {
-                    "backup.json": {
-                        "content": serde_json::to_string(&backup).unwrap()
-                    }
-                }
+                "files": files
             }))?;
 
-        if result.status() == 200 {
-            println!("Successfully pushed backup");
-        } else {
-            println!("Failed to push backup");
+        match result.status() {
+            200 => report::info(format!(
+                "Successfully pushed backup for the profile {}",
+                profile
+            )),
+            201 => {
+                // Remember the new gist, so other profiles are pushed to the same gist
+                let gist: Gist = result.into_json()?;
+                report::info(format!(
+                    "Successfully pushed backup for the profile {} to the new gist {}",
+                    profile, gist.id
+                ));
+                self.set_id(gist.id)?;
+            }
+            _ => report::error("Failed to push backup"),
         }
         Ok(())
     }
@@ -135,9 +240,10 @@ impl RemoteProvider for Github {
         .call()?
         .into_json()?;
 
-        println!("Open the following URL in your browser and enter the code.");
-        println!("{}", device_login.verification_uri.green());
-        println!("{}", device_login.user_code.blue().bold());
+        report::event(&report::Event::Login {
+            url: &device_login.verification_uri,
+            code: &device_login.user_code,
+        });
 
         let mut has_token = false;
 
@@ -161,7 +267,7 @@ impl RemoteProvider for Github {
                         .set_password(&poll_request.access_token.unwrap())
                         .unwrap();
                     has_token = true;
-                    println!("Successfull Login");
+                    report::info("Successfull Login");
                 }
                 LoginError::AccessDenied => panic!("Access Denied"),
                 LoginError::IncorrectClientCredentials => panic!("Incorrect Client Credentials"),
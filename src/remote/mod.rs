@@ -1,8 +1,8 @@
-use crate::Package;
+use crate::{profile::Profile, Package};
 use serde::{de, ser};
 use std::{
     error::Error,
-    fs::{create_dir, read_to_string, write},
+    fs::{create_dir_all, read_to_string, write},
 };
 
 pub mod github;
@@ -12,10 +12,13 @@ pub trait RemoteProvider {
     fn get_keyring() -> keyring::Entry;
     /// Initializes a new `RemoteProvider`
     fn new() -> Self;
-    /// Pulls a backup from a remote server.
-    fn pull(&self) -> Result<Vec<Package>, Box<dyn Error>>;
-    /// Pushes a backup to a remote server.
-    fn push(&self, backup: &[Package]) -> Result<(), Box<dyn Error>>;
+    /// Pulls the backup of the profile from a remote server.
+    /// The signature is checked against the trusted signers unless `verify` is false.
+    fn pull(&self, profile: &Profile, verify: bool) -> Result<Vec<Package>, Box<dyn Error>>;
+    /// Pushes the backup of the profile to a remote server.
+    /// The fields that only exist in backups are kept from the backup on the server, see [`crate::apply_previous`].
+    /// The backup is signed if a signing key is configured.
+    fn push(&self, backup: &[Package], profile: &Profile) -> Result<(), Box<dyn Error>>;
     /// Obtain a access token for the remote server.
     fn login(&self, relogin: bool) -> Result<(), Box<dyn Error>>;
     /// Set the id for the backup.
@@ -48,7 +51,7 @@ where
     let path = dirs::config_dir().unwrap().join("cargo-backup");
 
     if !path.exists() {
-        create_dir(&path).unwrap();
+        create_dir_all(&path).unwrap();
     }
 
     let path = path.join(format!("{}.toml", T::get_name()));
@@ -73,7 +76,7 @@ where
     let path = dirs::config_dir().unwrap().join("cargo-backup");
 
     if !path.exists() {
-        create_dir(&path).unwrap();
+        create_dir_all(&path).unwrap();
     }
 
     let path = path.join(format!("{}.toml", T::get_name()));
@@ -0,0 +1,671 @@
+use crate::{
+    check::{Problem, Severity},
+    color::{epaint, glyphs, paint, Stream},
+    diff::Diff,
+    history::{format_timestamp, Entry},
+    misc::{pretty_print_diff, pretty_print_plan},
+    plan::{describe_features, Action, Plan, Step},
+};
+use owo_colors::Style;
+use serde::Serialize;
+use std::{
+    fmt,
+    io::IsTerminal,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How important a message is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+    /// Only reported with `--verbose`.
+    Debug,
+}
+
+/// How much is reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only errors, warnings and results.
+    Quiet,
+    #[default]
+    Normal,
+    /// Debug messages as well.
+    Verbose,
+}
+
+/// Something worth telling the user about.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Message {
+        level: Level,
+        message: String,
+    },
+    /// The plan of a restore, before it is applied.
+    Plan {
+        plan: &'a Plan,
+        dry_run: bool,
+        /// The user is asked to confirm the plan or some of its steps next.
+        confirm: bool,
+    },
+    /// A step of a restore started.
+    Progress {
+        /// The number of the step, starting at 1.
+        current: usize,
+        total: usize,
+        step: &'a Step,
+        /// The estimated time until the restore finishes, based on previous restores.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        remaining_ms: Option<u64>,
+    },
+    /// A step of a restore finished.
+    Step {
+        step: &'a Step,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        duration_ms: u64,
+    },
+    Diff {
+        diff: &'a Diff,
+    },
+    /// A problem found by `cargo backup check`.
+    Problem {
+        problem: &'a Problem,
+    },
+    /// The user has to open the url and enter the code to log in.
+    Login {
+        url: &'a str,
+        code: &'a str,
+    },
+    /// A profile with a local backup, listed by `cargo backup profiles`.
+    Profile {
+        name: &'a str,
+        path: &'a Path,
+    },
+    /// A previous restore, listed by `cargo backup history`.
+    History {
+        entry: &'a Entry,
+    },
+}
+
+impl Event<'_> {
+    /// Checks if the event is reported with the verbosity.
+    fn is_shown(&self, verbosity: Verbosity) -> bool {
+        match self {
+            Event::Message { level, .. } => match level {
+                Level::Error | Level::Warning => true,
+                Level::Info => verbosity != Verbosity::Quiet,
+                Level::Debug => verbosity == Verbosity::Verbose,
+            },
+            // Never ask to confirm a plan the user did not see
+            Event::Plan {
+                dry_run, confirm, ..
+            } => *dry_run || *confirm || verbosity != Verbosity::Quiet,
+            Event::Progress { .. } => verbosity != Verbosity::Quiet,
+            Event::Step { error, .. } => error.is_some() || verbosity != Verbosity::Quiet,
+            Event::Diff { .. }
+            | Event::Problem { .. }
+            | Event::Login { .. }
+            | Event::Profile { .. }
+            | Event::History { .. } => true,
+        }
+    }
+}
+
+/// Receives everything the library and the binaries want to tell the user.
+///
+/// Implement this to route the output into your own UI.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::report::{self, Event, Reporter, Verbosity};
+///
+/// struct Prefixed;
+///
+/// impl Reporter for Prefixed {
+///     fn report(&self, event: &Event) {
+///         eprintln!("[provisioning] {:?}", event);
+///     }
+/// }
+///
+/// report::set(Prefixed, Verbosity::Verbose);
+/// ```
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: &Event);
+}
+
+/// The output formats of the binaries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Colored trees for humans.
+    #[default]
+    Human,
+    /// One line per item without colors, for grep and friends.
+    Plain,
+    /// One JSON object per line and event on stdout, with messages on stderr.
+    Json,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "plain" => Ok(Self::Plain),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown output format {:?}", s)),
+        }
+    }
+}
+
+type Current = (Arc<dyn Reporter>, Verbosity);
+
+static REPORTER: RwLock<Option<Current>> = RwLock::new(None);
+
+/// Replaces the reporter used by the library.
+/// Until this is called, events are reported by [`HumanReporter`].
+pub fn set(reporter: impl Reporter + 'static, verbosity: Verbosity) {
+    *REPORTER.write().unwrap() = Some((Arc::new(reporter), verbosity));
+}
+
+/// Uses the reporter of the output format.
+pub fn init(output: Output, verbosity: Verbosity) {
+    match output {
+        Output::Human => set(HumanReporter, verbosity),
+        Output::Plain => set(PlainReporter, verbosity),
+        Output::Json => set(JsonReporter, verbosity),
+    }
+}
+
+static PLANS_HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Stops reporting the plans of restores, e.g. because the plan is written to stdout as JSON instead.
+/// Plans that have to be confirmed are still reported.
+pub fn hide_plans() {
+    PLANS_HIDDEN.store(true, Ordering::Relaxed);
+}
+
+/// Reports the event if the verbosity allows it.
+pub fn event(event: &Event) {
+    let (reporter, verbosity): Current = REPORTER
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| (Arc::new(HumanReporter), Verbosity::Normal));
+
+    let hidden =
+        matches!(event, Event::Plan { confirm: false, .. }) && PLANS_HIDDEN.load(Ordering::Relaxed);
+
+    if event.is_shown(verbosity) && !hidden {
+        reporter.report(event);
+    }
+}
+
+pub fn message(level: Level, message: impl fmt::Display) {
+    event(&Event::Message {
+        level,
+        message: message.to_string(),
+    });
+}
+
+pub fn error(message: impl fmt::Display) {
+    self::message(Level::Error, message);
+}
+
+pub fn warn(message: impl fmt::Display) {
+    self::message(Level::Warning, message);
+}
+
+pub fn info(message: impl fmt::Display) {
+    self::message(Level::Info, message);
+}
+
+pub fn debug(message: impl fmt::Display) {
+    self::message(Level::Debug, message);
+}
+
+/// Formats a duration like `4.2s`, `3m05s` or `1h20m`.
+fn format_duration(ms: u64) -> String {
+    let seconds = ms / 1000;
+    if seconds < 60 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else if seconds < 3600 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// Describes a started step, e.g. `[2/5] installing ripgrep`.
+fn describe_progress(current: usize, total: usize, step: &Step) -> String {
+    let phase = match step.action {
+        Action::Install => "installing",
+        Action::Update => "updating",
+        Action::Downgrade => "downgrading",
+        Action::Reconfigure => "reconfiguring",
+        Action::Remove => "removing",
+    };
+    format!("[{}/{}] {} {}", current, total, phase, step.package.name)
+}
+
+fn describe_remaining(remaining_ms: Option<u64>) -> Option<String> {
+    match remaining_ms {
+        Some(ms) if ms > 0 => Some(format!("about {} left", format_duration(ms))),
+        _ => None,
+    }
+}
+
+/// Describes a started step with the estimated remaining time, if there is one.
+fn describe_progress_line(
+    current: usize,
+    total: usize,
+    step: &Step,
+    remaining_ms: Option<u64>,
+) -> String {
+    let text = describe_progress(current, total, step);
+    match describe_remaining(remaining_ms) {
+        Some(remaining) => format!("{} ({})", text, remaining),
+        None => text,
+    }
+}
+
+/// Describes a finished step, e.g. `installed ripgrep 14.0.0`.
+fn describe_step(step: &Step, error: &Option<String>) -> String {
+    let verb = match (step.action, error.is_some()) {
+        (Action::Install, false) => "installed",
+        (Action::Update, false) => "updated",
+        (Action::Downgrade, false) => "downgraded",
+        (Action::Reconfigure, false) => "reconfigured",
+        (Action::Remove, false) => "removed",
+        (Action::Install, true) => "failed to install",
+        (Action::Update, true) => "failed to update",
+        (Action::Downgrade, true) => "failed to downgrade",
+        (Action::Reconfigure, true) => "failed to reconfigure",
+        (Action::Remove, true) => "failed to remove",
+    };
+
+    match error {
+        Some(error) => format!("{} {}: {}", verb, step.package.name, error),
+        None => format!("{} {} {}", verb, step.package.name, step.package.version),
+    }
+}
+
+fn print_commands(plan: &Plan) {
+    println!("Dry run, the following commands would run:");
+    for step in plan.steps() {
+        println!("  {}", step.command.join(" "));
+    }
+}
+
+/// The step shown on the last line of a terminal, with its elapsed time.
+struct Status {
+    text: String,
+    started: Instant,
+    remaining_ms: Option<u64>,
+}
+
+impl Status {
+    fn draw(&self) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        let time = match describe_remaining(self.remaining_ms.map(|ms| ms.saturating_sub(elapsed)))
+        {
+            Some(remaining) => format!("{}, {}", format_duration(elapsed), remaining),
+            None => format_duration(elapsed),
+        };
+        eprint!(
+            "\r\x1b[2K{} {}",
+            self.text,
+            epaint(time, Style::new().bright_black())
+        );
+    }
+}
+
+static STATUS: Mutex<Option<Status>> = Mutex::new(None);
+static TICKER: Once = Once::new();
+
+/// Redraws the status line until the process exits, so the elapsed time keeps counting.
+fn start_ticker() {
+    TICKER.call_once(|| {
+        thread::spawn(|| loop {
+            thread::sleep(Duration::from_millis(250));
+            if let Some(status) = STATUS.lock().unwrap().as_ref() {
+                status.draw();
+            }
+        });
+    });
+}
+
+/// Prints colored trees and messages.
+///
+/// While a restore runs on a terminal, the current step is shown on the last line of stderr.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HumanReporter;
+
+impl Reporter for HumanReporter {
+    fn report(&self, event: &Event) {
+        let mut status = STATUS.lock().unwrap();
+        if status.is_some() {
+            eprint!("\r\x1b[2K");
+        }
+
+        match event {
+            Event::Progress {
+                current,
+                total,
+                step,
+                remaining_ms,
+            } => {
+                if std::io::stderr().is_terminal() {
+                    *status = Some(Status {
+                        text: describe_progress(*current, *total, step),
+                        started: Instant::now(),
+                        remaining_ms: *remaining_ms,
+                    });
+                    start_ticker();
+                } else {
+                    eprintln!(
+                        "{}",
+                        describe_progress_line(*current, *total, step, *remaining_ms)
+                    );
+                }
+            }
+            Event::Step { .. } => {
+                *status = None;
+                self.print(event);
+            }
+            _ => self.print(event),
+        }
+
+        if let Some(status) = status.as_ref() {
+            status.draw();
+        }
+    }
+}
+
+impl HumanReporter {
+    fn print(&self, event: &Event) {
+        match event {
+            Event::Message { level, message } => match level {
+                Level::Error => {
+                    eprintln!(
+                        "{} {}",
+                        epaint("Error:", Style::new().red().bold()),
+                        message
+                    )
+                }
+                Level::Warning => eprintln!(
+                    "{} {}",
+                    epaint("Warning:", Style::new().yellow().bold()),
+                    message
+                ),
+                Level::Info => eprintln!("{}", message),
+                Level::Debug => eprintln!("{}", epaint(message, Style::new().bright_black())),
+            },
+            Event::Plan { plan, dry_run, .. } => {
+                pretty_print_plan(plan);
+                if *dry_run {
+                    print_commands(plan);
+                }
+            }
+            Event::Progress { .. } => {}
+            Event::Step {
+                step,
+                error: None,
+                duration_ms,
+            } => {
+                eprintln!(
+                    "{} {} {}",
+                    epaint(glyphs(Stream::Stderr).ok, Style::new().green()),
+                    describe_step(step, &None),
+                    epaint(
+                        format!("({})", format_duration(*duration_ms)),
+                        Style::new().bright_black()
+                    )
+                );
+            }
+            Event::Step { step, error, .. } => {
+                eprintln!(
+                    "{} {}",
+                    epaint(glyphs(Stream::Stderr).failed, Style::new().red()),
+                    describe_step(step, error)
+                );
+            }
+            Event::Diff { diff } if diff.is_empty() => println!("No differences"),
+            Event::Diff { diff } => pretty_print_diff(diff),
+            Event::Problem { problem } => {
+                match problem.severity {
+                    Severity::Error => print!("{}", paint("error", Style::new().red().bold())),
+                    Severity::Warning => {
+                        print!("{}", paint("warning", Style::new().yellow().bold()))
+                    }
+                }
+                println!(
+                    ": {} {}",
+                    paint(&problem.path, Style::new().cyan()),
+                    problem.message
+                );
+            }
+            Event::Login { url, code } => {
+                println!("Open the following URL in your browser and enter the code.");
+                println!("{}", paint(url, Style::new().green()));
+                println!("{}", paint(code, Style::new().blue().bold()));
+            }
+            Event::Profile { name, path } => println!(
+                "{} {}",
+                paint(name, Style::new().cyan().bold()),
+                path.display()
+            ),
+            Event::History { entry } => {
+                println!(
+                    "{} {} ({})",
+                    paint(format_timestamp(entry.timestamp), Style::new().bold()),
+                    entry.command.join(" "),
+                    format_duration(entry.duration_ms)
+                );
+
+                for source in &entry.sources {
+                    println!("  from {}", source);
+                }
+
+                for outcome in &entry.outcomes {
+                    let action = serde_json::to_value(outcome.action).unwrap();
+                    let status = match &outcome.error {
+                        None => paint("ok", Style::new().green()),
+                        Some(e) => paint(format!("failed: {}", e), Style::new().red()),
+                    };
+                    println!(
+                        "  {} {} {} ({})",
+                        action.as_str().unwrap(),
+                        paint(&outcome.package, Style::new().cyan().bold()),
+                        status,
+                        format_duration(outcome.duration_ms)
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Prints one line per item without colors.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PlainReporter;
+
+impl Reporter for PlainReporter {
+    fn report(&self, event: &Event) {
+        match event {
+            Event::Message { level, message } => match level {
+                Level::Error => eprintln!("error: {}", message),
+                Level::Warning => eprintln!("warning: {}", message),
+                Level::Info | Level::Debug => eprintln!("{}", message),
+            },
+            Event::Plan { plan, dry_run, .. } => {
+                for step in plan.steps() {
+                    let action = serde_json::to_value(step.action).unwrap();
+                    println!(
+                        "{} {} {} ({})",
+                        action.as_str().unwrap(),
+                        step.package.name,
+                        step.package.version,
+                        step.reason
+                    );
+                }
+                for package in &plan.skipped {
+                    println!("skip {} {}", package.name, package.version);
+                }
+                if *dry_run {
+                    print_commands(plan);
+                }
+            }
+            Event::Progress {
+                current,
+                total,
+                step,
+                remaining_ms,
+            } => println!(
+                "{}",
+                describe_progress_line(*current, *total, step, *remaining_ms)
+            ),
+            Event::Step {
+                step,
+                error: None,
+                duration_ms,
+            } => println!(
+                "{} in {}",
+                describe_step(step, &None),
+                format_duration(*duration_ms)
+            ),
+            Event::Step { step, error, .. } => println!("{}", describe_step(step, error)),
+            Event::Diff { diff } => {
+                for p in &diff.added {
+                    println!("added {} {}", p.name, p.version);
+                }
+                for p in &diff.removed {
+                    println!("removed {} {}", p.name, p.version);
+                }
+                for c in &diff.version_changed {
+                    println!(
+                        "version {} {} -> {}",
+                        c.to.name, c.from.version, c.to.version
+                    );
+                }
+                for c in &diff.features_changed {
+                    println!(
+                        "features {} [{}] -> [{}]",
+                        c.to.name,
+                        describe_features(&c.from),
+                        describe_features(&c.to)
+                    );
+                }
+            }
+            Event::Problem { problem } => println!("{}", problem),
+            Event::Login { url, code } => println!("login {} {}", url, code),
+            Event::Profile { name, path } => println!("{} {}", name, path.display()),
+            Event::History { entry } => {
+                // Every line starts with the time of the restore, so they can be grepped
+                let timestamp = format_timestamp(entry.timestamp);
+                println!(
+                    "{} {} in {}",
+                    timestamp,
+                    entry.command.join(" "),
+                    format_duration(entry.duration_ms)
+                );
+                for source in &entry.sources {
+                    println!("{} from {}", timestamp, source);
+                }
+                for outcome in &entry.outcomes {
+                    let action = serde_json::to_value(outcome.action).unwrap();
+                    let action = action.as_str().unwrap();
+                    match &outcome.error {
+                        None => println!(
+                            "{} {} {} ok in {}",
+                            timestamp,
+                            action,
+                            outcome.package,
+                            format_duration(outcome.duration_ms)
+                        ),
+                        Some(e) => {
+                            println!("{} {} {} failed: {}", timestamp, action, outcome.package, e)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Prints every event as a JSON object on its own line.
+///
+/// Messages are printed to stderr, so they never end up in data written to stdout, e.g. a backup of `-o -`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn report(&self, event: &Event) {
+        let json = serde_json::to_string(event).expect("Failed to serialize");
+        match event {
+            Event::Message { .. } => eprintln!("{}", json),
+            _ => println!("{}", json),
+        }
+    }
+}
+
+#[test]
+fn test_event_is_shown() {
+    let debug = Event::Message {
+        level: Level::Debug,
+        message: String::from("running cargo"),
+    };
+    assert!(!debug.is_shown(Verbosity::Normal));
+    assert!(debug.is_shown(Verbosity::Verbose));
+
+    let plan = Plan::default();
+    let event = Event::Plan {
+        plan: &plan,
+        dry_run: false,
+        confirm: false,
+    };
+    assert!(!event.is_shown(Verbosity::Quiet));
+    assert!(Event::Plan {
+        plan: &plan,
+        dry_run: false,
+        confirm: true,
+    }
+    .is_shown(Verbosity::Quiet));
+    assert_eq!(
+        serde_json::to_string(&event).unwrap(),
+        r#"{"type":"plan","plan":{"installs":[],"updates":[],"downgrades":[],"reconfigures":[],"removals":[],"skipped":[]},"dry_run":false,"confirm":false}"#
+    );
+
+    let entry = Entry {
+        timestamp: 0,
+        command: vec![String::from("cargo-restore")],
+        sources: vec![],
+        plan,
+        outcomes: vec![],
+        duration_ms: 0,
+    };
+    let event = Event::History { entry: &entry };
+    assert!(event.is_shown(Verbosity::Quiet));
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({ "type": "history", "entry": entry })
+    );
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(4_240), "4.2s");
+    assert_eq!(format_duration(185_000), "3m05s");
+    assert_eq!(format_duration(4_800_000), "1h20m");
+}
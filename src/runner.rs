@@ -0,0 +1,193 @@
+use crate::{
+    plan::{Action, Step},
+    report,
+};
+use std::{
+    error::Error,
+    fmt,
+    fs::{create_dir_all, File},
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Runs the steps of a restore.
+///
+/// Implement this to run the commands somewhere else, e.g. inside a container.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{apply_plan, runner::CargoRunner, Confirmation};
+///
+/// apply_plan(&plan, Confirmation::Never, &mut CargoRunner::default()).unwrap();
+/// ```
+pub trait Runner {
+    /// Installs, updates or reinstalls the package of the step.
+    fn install(&mut self, step: &Step) -> Result<(), RunError>;
+    /// Uninstalls the package of the step.
+    fn uninstall(&mut self, step: &Step) -> Result<(), RunError>;
+
+    /// Returns the file the output of the step is written to, if any.
+    fn log_path(&self, _step: &Step) -> Option<PathBuf> {
+        None
+    }
+
+    /// Runs the step with [`Runner::install`] or [`Runner::uninstall`].
+    fn run(&mut self, step: &Step) -> Result<(), RunError> {
+        match step.action {
+            Action::Remove => self.uninstall(step),
+            _ => self.install(step),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RunError {
+    /// The command could not be started.
+    Spawn(String),
+    /// The command exited with a non zero status.
+    Failed(Option<i32>),
+    /// The log file could not be written.
+    Log(String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(e) => write!(f, "failed to run cargo: {}", e),
+            Self::Failed(Some(code)) => write!(f, "cargo exited with status {}", code),
+            Self::Failed(None) => write!(f, "cargo was terminated by a signal"),
+            Self::Log(e) => write!(f, "failed to write the log: {}", e),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+/// Runs the cargo command of each step on this machine.
+///
+/// With a log directory, the output of each package is written to `<log dir>/<package>.log`
+/// and only reported as debug messages.
+#[derive(Debug, Default, Clone)]
+pub struct CargoRunner {
+    log_dir: Option<PathBuf>,
+}
+
+impl CargoRunner {
+    pub fn with_log_dir(log_dir: PathBuf) -> Self {
+        Self {
+            log_dir: Some(log_dir),
+        }
+    }
+
+    fn execute(&self, step: &Step) -> Result<(), RunError> {
+        let mut command = Command::new(&step.command[0]);
+        command.args(&step.command[1..]).envs(&step.env);
+
+        let Some(path) = self.log_path(step) else {
+            let status = command
+                .status()
+                .map_err(|e| RunError::Spawn(e.to_string()))?;
+            return check_status(status);
+        };
+
+        create_dir_all(path.parent().unwrap()).map_err(|e| RunError::Log(e.to_string()))?;
+        let log = File::create(&path).map_err(|e| RunError::Log(e.to_string()))?;
+        let log = Arc::new(Mutex::new(log));
+
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| RunError::Spawn(e.to_string()))?;
+
+        let stdout = tee(child.stdout.take().unwrap(), Arc::clone(&log));
+        let stderr = tee(child.stderr.take().unwrap(), Arc::clone(&log));
+        let _ = stdout.join();
+        let _ = stderr.join();
+
+        let status = child.wait().map_err(|e| RunError::Spawn(e.to_string()))?;
+        check_status(status)
+    }
+}
+
+/// Writes each line of the output to the log and reports it as a debug message.
+fn tee(output: impl Read + Send + 'static, log: Arc<Mutex<File>>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            let _ = writeln!(log.lock().unwrap(), "{}", line);
+            report::debug(line);
+        }
+    })
+}
+
+fn check_status(status: std::process::ExitStatus) -> Result<(), RunError> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(RunError::Failed(status.code()))
+    }
+}
+
+impl Runner for CargoRunner {
+    fn install(&mut self, step: &Step) -> Result<(), RunError> {
+        self.execute(step)
+    }
+
+    fn uninstall(&mut self, step: &Step) -> Result<(), RunError> {
+        self.execute(step)
+    }
+
+    fn log_path(&self, step: &Step) -> Option<PathBuf> {
+        self.log_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.log", step.package.name)))
+    }
+}
+
+/// Records the steps instead of running them, for tests and previews.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::{apply_plan, runner::RecordingRunner, Confirmation};
+///
+/// let mut runner = RecordingRunner::default().failing("cargo-edit");
+/// let outcomes = apply_plan(&plan, Confirmation::Never, &mut runner).unwrap();
+/// assert_eq!(runner.steps.len(), outcomes.len());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RecordingRunner {
+    /// The steps in the order they were run.
+    pub steps: Vec<Step>,
+    fail: Vec<String>,
+}
+
+impl RecordingRunner {
+    /// Makes every step of the package fail.
+    pub fn failing(mut self, name: &str) -> Self {
+        self.fail.push(name.to_string());
+        self
+    }
+
+    fn record(&mut self, step: &Step) -> Result<(), RunError> {
+        self.steps.push(step.clone());
+
+        if self.fail.contains(&step.package.name) {
+            Err(RunError::Failed(Some(101)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Runner for RecordingRunner {
+    fn install(&mut self, step: &Step) -> Result<(), RunError> {
+        self.record(step)
+    }
+
+    fn uninstall(&mut self, step: &Step) -> Result<(), RunError> {
+        self.record(step)
+    }
+}
@@ -0,0 +1,366 @@
+use crate::Package;
+use semver::Version;
+use serde_json::{json, Value};
+use std::{error::Error, fmt};
+
+/// Returns the JSON Schema of a backup document.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::schema::schema;
+///
+/// println!("{}", serde_json::to_string_pretty(&schema()).unwrap());
+/// ```
+pub fn schema() -> Value {
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "https://github.com/Kiramily/cargo-backup/backup.schema.json",
+        "title": "cargo-backup backup",
+        "description": "A list of installed cargo packages created by cargo-backup",
+        "type": "array",
+        "items": { "$ref": "#/$defs/package" },
+        "$defs": {
+            "package": {
+                "type": "object",
+                "required": ["name", "features", "all_features", "no_default_features", "version"],
+                "properties": {
+                    "name": {
+                        "description": "The name of the crate",
+                        "type": "string",
+                        "minLength": 1,
+                        "maxLength": 64,
+                        "pattern": CRATE_NAME_PATTERN
+                    },
+                    "features": {
+                        "description": "The features passed to `cargo install --features`",
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "all_features": {
+                        "description": "Whether the crate is installed with `--all-features`",
+                        "type": "boolean"
+                    },
+                    "no_default_features": {
+                        "description": "Whether the crate is installed with `--no-default-features`",
+                        "type": "boolean"
+                    },
+                    "version": {
+                        "description": "The installed version of the crate",
+                        "type": "string",
+                        "format": "semver"
+                    },
+                    "groups": {
+                        "description": "The groups of the package, used with `cargo restore --group`",
+                        "type": "array",
+                        "items": { "type": "string", "minLength": 1 }
+                    },
+                    "only_on": {
+                        "description": "Only install the package on platforms matching the `cfg(...)` expression or target triple pattern",
+                        "type": "string",
+                        "minLength": 1
+                    },
+                    "overrides": {
+                        "description": "Per platform overrides of the install options",
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/override" }
+                    },
+                    "args": {
+                        "description": "Extra arguments passed to `cargo install`",
+                        "type": "array",
+                        "items": { "type": "string", "minLength": 1 }
+                    },
+                    "env": {
+                        "description": "Environment variables set for `cargo install`",
+                        "type": "object",
+                        "additionalProperties": { "type": "string" }
+                    },
+                    "toolchain": {
+                        "description": "The rustup toolchain used to install the crate, e.g. `nightly`",
+                        "type": "string",
+                        "minLength": 1
+                    },
+                    "priority": {
+                        "description": "Packages with a higher priority are installed first",
+                        "type": "integer"
+                    },
+                    "after": {
+                        "description": "The packages that have to be installed or updated before this one",
+                        "type": "array",
+                        "items": { "type": "string", "minLength": 1 }
+                    }
+                }
+            },
+            "override": {
+                "type": "object",
+                "required": ["when"],
+                "properties": {
+                    "when": {
+                        "description": "A `cfg(...)` expression or target triple pattern",
+                        "type": "string",
+                        "minLength": 1
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" }
+                    },
+                    "all_features": { "type": "boolean" },
+                    "no_default_features": { "type": "boolean" }
+                }
+            }
+        }
+    })
+}
+
+/// A single violation of the schema.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// The location of the offending value, e.g. `$[2].version`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The document is not valid JSON.
+    Json(serde_json::Error),
+    /// The document does not match the schema.
+    Invalid(Vec<Violation>),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "failed to parse json: {}", e),
+            Self::Invalid(violations) => {
+                write!(f, "backup does not match the schema:")?;
+                for violation in violations {
+                    write!(f, "\n  {}", violation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+/// Validates the document against the backup schema.
+pub fn validate(document: &Value) -> Vec<Violation> {
+    let schema = schema();
+    let mut violations = vec![];
+    validate_value(&schema, &schema, document, "$", &mut violations);
+    violations
+}
+
+/// Parses and validates a backup document.
+///
+/// # Examples
+/// ```no_run
+/// use cargo_backup::schema::parse;
+///
+/// let packages = parse(r#"[{"name": "foo", "version": "1.x"}]"#);
+/// assert!(packages.is_err());
+/// ```
+pub fn parse(content: &str) -> Result<Vec<Package>, SchemaError> {
+    let document: Value = serde_json::from_str(content).map_err(SchemaError::Json)?;
+
+    let violations = validate(&document);
+    if !violations.is_empty() {
+        return Err(SchemaError::Invalid(violations));
+    }
+
+    serde_json::from_value(document).map_err(SchemaError::Json)
+}
+
+/// Validates a value against the subset of JSON Schema used by [`schema`].
+fn validate_value(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    violations: &mut Vec<Violation>,
+) {
+    let mut violation = |message: String| {
+        violations.push(Violation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let pointer = reference.trim_start_matches('#');
+        if let Some(schema) = root.pointer(pointer) {
+            validate_value(root, schema, value, path, violations);
+        }
+        return;
+    }
+
+    if let Some(expected) = schema["type"].as_str() {
+        let matches = match expected {
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+
+        if !matches {
+            violation(format!("expected {}, found {}", expected, type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(s) = value.as_str() {
+        if let Some(min) = schema["minLength"].as_u64() {
+            if (s.chars().count() as u64) < min {
+                violation(format!("must be at least {} characters long", min));
+            }
+        }
+
+        if let Some(max) = schema["maxLength"].as_u64() {
+            if (s.chars().count() as u64) > max {
+                violation(format!("must be at most {} characters long", max));
+            }
+        }
+
+        // The only pattern of the schema, so it is checked without a regex engine
+        if schema["pattern"] == CRATE_NAME_PATTERN && !is_valid_crate_name(s) {
+            violation(format!("{:?} is not a valid crate name", s));
+        }
+
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                violation(format!("{:?} is not one of {}", s, schema["enum"]));
+            }
+        }
+
+        if schema["format"] == "semver" {
+            if let Err(e) = Version::parse(s) {
+                violation(format!("{:?} is not a valid semver version ({})", s, e));
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        for (i, item) in items.iter().enumerate() {
+            validate_value(
+                root,
+                &schema["items"],
+                item,
+                &format!("{}[{}]", path, i),
+                violations,
+            );
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(required) = schema["required"].as_array() {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    violations.push(Violation {
+                        path: path.to_string(),
+                        message: format!("missing required field {:?}", key),
+                    });
+                }
+            }
+        }
+
+        let properties = schema["properties"].as_object();
+        for (key, value) in object {
+            let schema = match properties.and_then(|properties| properties.get(key)) {
+                Some(schema) => schema,
+                None if schema["additionalProperties"].is_object() => {
+                    &schema["additionalProperties"]
+                }
+                None => continue,
+            };
+
+            validate_value(
+                root,
+                schema,
+                value,
+                &format!("{}.{}", path, key),
+                violations,
+            );
+        }
+    }
+}
+
+const CRATE_NAME_PATTERN: &str = "^[A-Za-z][A-Za-z0-9_-]*$";
+
+/// Checks if the name matches [`CRATE_NAME_PATTERN`], so it can never be taken for an option of cargo.
+fn is_valid_crate_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[test]
+fn test_validate() {
+    let document = json!([
+        {
+            "name": "foo",
+            "features": [],
+            "all_features": false,
+            "no_default_features": false,
+            "version": "0.1.0"
+        },
+        {
+            "name": "bar",
+            "features": ["a", 1],
+            "all_features": "yes",
+            "version": "1.x",
+            "env": { "RUSTFLAGS": 1 }
+        }
+    ]);
+
+    let paths: Vec<String> = validate(&document)
+        .into_iter()
+        .map(|violation| violation.path)
+        .collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            "$[1]",
+            "$[1].all_features",
+            "$[1].env.RUSTFLAGS",
+            "$[1].features[1]",
+            "$[1].version"
+        ]
+    );
+}
+
+#[test]
+fn test_parse_rejects_options_as_names() {
+    let content = r#"[{"name": "--git=https://evil.example/x", "features": [], "all_features": false, "no_default_features": false, "version": "1.0.0"}]"#;
+
+    match parse(content) {
+        Err(SchemaError::Invalid(violations)) => {
+            assert_eq!(violations.len(), 1);
+            assert_eq!(violations[0].path, "$[0].name");
+        }
+        result => panic!("expected an invalid name, got {:?}", result),
+    }
+}
@@ -0,0 +1,251 @@
+use crate::remote::{get_config, ProviderConfig};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    fs::{self, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The namespace used for `ssh-keygen -Y sign` and `ssh-keygen -Y verify`.
+const NAMESPACE: &str = "cargo-backup";
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Config {
+    /// The ssh private key used to sign backups.
+    pub key: Option<PathBuf>,
+    /// The ssh `allowed_signers` file containing the trusted signers.
+    /// Defaults to `allowed_signers` inside the config directory.
+    pub allowed_signers: Option<PathBuf>,
+}
+
+impl ProviderConfig for Config {
+    fn get_name() -> String {
+        String::from("signing")
+    }
+}
+
+impl Config {
+    /// Loads the signing config from the config directory.
+    pub fn load() -> Self {
+        get_config()
+    }
+
+    /// Returns the path to the `allowed_signers` file if it exists.
+    pub fn allowed_signers(&self) -> Option<PathBuf> {
+        let path = match &self.allowed_signers {
+            Some(path) => PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).as_ref()),
+            None => dirs::config_dir()?
+                .join("cargo-backup")
+                .join("allowed_signers"),
+        };
+
+        path.exists().then_some(path)
+    }
+}
+
+#[derive(Debug)]
+pub enum SignError {
+    /// The backup has no signature but trusted signers are configured.
+    Missing,
+    /// The signature does not match the backup or was not made by a trusted signer.
+    Invalid(String),
+    /// `ssh-keygen` could not be executed.
+    Command(String),
+    /// A signature was given but there are no trusted signers to verify it against.
+    NoTrustedSigners,
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "backup is not signed"),
+            Self::Invalid(reason) => write!(f, "invalid backup signature: {}", reason),
+            Self::Command(reason) => write!(f, "failed to run ssh-keygen: {}", reason),
+            Self::NoTrustedSigners => write!(
+                f,
+                "cannot verify the signature because no allowed_signers file is configured"
+            ),
+        }
+    }
+}
+
+impl Error for SignError {}
+
+/// Returns the path of the sidecar signature for a backup file.
+///
+/// # Examples
+/// ```no_run
+/// let path = signature_path(Path::new("backup.json"));
+/// assert_eq!(path, PathBuf::from("backup.json.sig"));
+/// ```
+pub fn signature_path(backup: &Path) -> PathBuf {
+    let mut path = backup.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Signs the backup content with the given ssh private key and returns the armored signature.
+pub fn sign(content: &[u8], key: &Path) -> Result<String, SignError> {
+    let key = shellexpand::tilde(&key.to_string_lossy()).to_string();
+
+    let output = run_ssh_keygen(&["-Y", "sign", "-n", NAMESPACE, "-f", &key], content)?;
+
+    if !output.status.success() {
+        return Err(SignError::Command(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Verifies the signature of the backup content against the trusted signers
+/// and returns the principal that signed it.
+pub fn verify(
+    content: &[u8],
+    signature: &str,
+    allowed_signers: &Path,
+) -> Result<String, SignError> {
+    let sig_path =
+        write_temp_signature(signature).map_err(|e| SignError::Command(e.to_string()))?;
+
+    let result = verify_with_file(content, &sig_path, allowed_signers);
+    let _ = fs::remove_file(&sig_path);
+    result
+}
+
+/// Writes the signature to a new file with an unpredictable name in the temp directory.
+/// The file is created exclusively, so an existing file or symlink of another user is never written to.
+fn write_temp_signature(signature: &str) -> std::io::Result<PathBuf> {
+    let mut attempts = 0;
+    loop {
+        // RandomState is seeded randomly for every process and hasher
+        let random = RandomState::new().build_hasher().finish();
+        let path = std::env::temp_dir().join(format!(
+            "cargo-backup-{}-{:016x}.sig",
+            std::process::id(),
+            random
+        ));
+
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(signature.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && attempts < 10 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn verify_with_file(
+    content: &[u8],
+    sig_path: &Path,
+    allowed_signers: &Path,
+) -> Result<String, SignError> {
+    let sig_path = sig_path.to_string_lossy();
+    let allowed_signers = allowed_signers.to_string_lossy();
+
+    let output = run_ssh_keygen(
+        &[
+            "-Y",
+            "find-principals",
+            "-s",
+            &sig_path,
+            "-f",
+            &allowed_signers,
+        ],
+        &[],
+    )?;
+
+    let principal = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| output.status.success() && !line.is_empty())
+        .ok_or_else(|| SignError::Invalid("signer is not trusted".to_string()))?;
+
+    let output = run_ssh_keygen(
+        &[
+            "-Y",
+            "verify",
+            "-n",
+            NAMESPACE,
+            "-s",
+            &sig_path,
+            "-f",
+            &allowed_signers,
+            "-I",
+            &principal,
+        ],
+        content,
+    )?;
+
+    if output.status.success() {
+        Ok(principal)
+    } else {
+        Err(SignError::Invalid(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Checks the backup against the configured trusted signers.
+///
+/// Returns the signing principal if the signature was verified, or `None` if no
+/// trusted signers are configured and the check was skipped.
+/// Callers that were explicitly given a signature should treat `None` as [`SignError::NoTrustedSigners`].
+pub fn check(content: &[u8], signature: Option<&str>) -> Result<Option<String>, SignError> {
+    let Some(allowed_signers) = Config::load().allowed_signers() else {
+        return Ok(None);
+    };
+
+    match signature {
+        Some(signature) => verify(content, signature, &allowed_signers).map(Some),
+        None => Err(SignError::Missing),
+    }
+}
+
+fn run_ssh_keygen(args: &[&str], stdin: &[u8]) -> Result<std::process::Output, SignError> {
+    let mut child = Command::new("ssh-keygen")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SignError::Command(e.to_string()))?;
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin)
+        .map_err(|e| SignError::Command(e.to_string()))?;
+
+    child
+        .wait_with_output()
+        .map_err(|e| SignError::Command(e.to_string()))
+}
+
+#[test]
+fn test_write_temp_signature() {
+    let a = write_temp_signature("signature").unwrap();
+    let b = write_temp_signature("signature").unwrap();
+    assert_ne!(a, b);
+    assert_eq!(fs::read_to_string(&a).unwrap(), "signature");
+    let _ = fs::remove_file(a);
+    let _ = fs::remove_file(b);
+}
+
+#[test]
+fn test_signature_path() {
+    assert_eq!(
+        signature_path(Path::new("backup.json")),
+        PathBuf::from("backup.json.sig")
+    );
+}
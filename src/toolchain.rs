@@ -0,0 +1,81 @@
+use std::{
+    error::Error,
+    fmt,
+    process::{Command, Stdio},
+};
+
+/// Returns the toolchain channel a package was built with,
+/// from the `rustc` field of `.crates2.json` like `rustc 1.77.0-nightly (3a85a5cfe 2024-01-23)`.
+///
+/// Stable builds return `None`, so they are installed with the default toolchain
+/// instead of being pinned to the rustc version they happened to be built with.
+pub fn from_rustc(rustc: &str) -> Option<String> {
+    let version = rustc.split_whitespace().nth(1)?;
+    let (_, pre) = version.split_once('-')?;
+    let channel = pre.split('.').next()?;
+
+    matches!(channel, "nightly" | "beta").then(|| channel.to_string())
+}
+
+#[derive(Debug)]
+pub enum ToolchainError {
+    /// `rustup` could not be executed.
+    Rustup(String),
+    /// Installing the toolchain failed.
+    Install(String),
+}
+
+impl fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rustup(e) => write!(f, "rustup is required to select toolchains: {}", e),
+            Self::Install(toolchain) => write!(f, "failed to install toolchain {}", toolchain),
+        }
+    }
+}
+
+impl Error for ToolchainError {}
+
+/// Checks if the toolchain is installed with rustup, either by its full name like
+/// `nightly-x86_64-unknown-linux-gnu` or by its channel for the host triple.
+pub fn is_installed(toolchain: &str, triple: &str) -> Result<bool, ToolchainError> {
+    let output = Command::new("rustup")
+        .args(["toolchain", "list"])
+        .output()
+        .map_err(|e| ToolchainError::Rustup(e.to_string()))?;
+
+    let with_triple = format!("{}-{}", toolchain, triple);
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|name| name == toolchain || name == with_triple))
+}
+
+/// Installs the toolchain with the minimal rustup profile.
+pub fn install(toolchain: &str) -> Result<(), ToolchainError> {
+    let status = Command::new("rustup")
+        .args(["toolchain", "install", toolchain, "--profile", "minimal"])
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| ToolchainError::Rustup(e.to_string()))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ToolchainError::Install(toolchain.to_string()))
+    }
+}
+
+#[test]
+fn test_from_rustc() {
+    assert_eq!(from_rustc("rustc 1.75.0 (82e1608df 2023-12-21)"), None);
+    assert_eq!(
+        from_rustc("rustc 1.77.0-nightly (3a85a5cfe 2024-01-23)"),
+        Some("nightly".to_string())
+    );
+    assert_eq!(
+        from_rustc("rustc 1.76.0-beta.5 (f732c37b4 2024-01-21)"),
+        Some("beta".to_string())
+    );
+    assert_eq!(from_rustc(""), None);
+}
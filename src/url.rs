@@ -26,7 +26,6 @@ impl UrlBuilder {
     }
 
     /// Adds a parameter to the url.
-
     pub fn add_param(&mut self, key: &str, value: &str) -> &mut Self {
         self.params.insert(key.to_string(), value.to_string());
         self
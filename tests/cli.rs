@@ -0,0 +1,79 @@
+#![cfg(unix)]
+
+use cargo_backup::{history::Entry, plan::Plan, Package};
+use common::{backup, package, path_with, rustup_log, setup};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+mod common;
+
+/// Runs `cargo restore` without a terminal, with an interrupted restore waiting to be resumed.
+fn restore(name: &str, packages: &[Package], args: &[&str]) -> (PathBuf, String) {
+    let root = setup(name);
+    fs::write(
+        root.join("backup.json"),
+        serde_json::to_string(packages).unwrap(),
+    )
+    .unwrap();
+
+    let unfinished = Entry {
+        timestamp: 0,
+        command: vec!["cargo-restore".to_string()],
+        sources: vec![],
+        plan: Plan::default(),
+        outcomes: vec![],
+        duration_ms: 0,
+    };
+    fs::create_dir_all(root.join("data")).unwrap();
+    fs::write(
+        root.join("data/unfinished.json"),
+        serde_json::to_string(&unfinished).unwrap(),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-restore"))
+        .args(["restore", "-b"])
+        .arg(root.join("backup.json"))
+        .args(args)
+        .env("HOME", &root)
+        .env("XDG_CONFIG_HOME", root.join("config"))
+        .env("CARGO_HOME", root.join("cargo"))
+        .env("CARGO_BACKUP_DATA_DIR", root.join("data"))
+        .env("PATH", path_with(&root))
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    // Nothing ran, so the interrupted restore can still be resumed
+    assert_eq!(
+        fs::read_to_string(root.join("data/unfinished.json")).unwrap(),
+        serde_json::to_string(&unfinished).unwrap()
+    );
+    assert_eq!(rustup_log(&root), None);
+
+    (root, String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+#[test]
+fn test_restore_not_interactive() {
+    let (root, stderr) = restore("not-interactive", &backup(), &[]);
+    assert!(stderr.contains("terminal is not interactive"), "{}", stderr);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_restore_not_allowed() {
+    let packages = vec![Package {
+        env: BTreeMap::from([("RUSTFLAGS".to_string(), "-C target-cpu=native".to_string())]),
+        ..package("ripgrep", "14.0.0")
+    }];
+
+    let (root, stderr) = restore("not-allowed", &packages, &["--yes"]);
+    assert!(stderr.contains("not allowed to install with"), "{}", stderr);
+    fs::remove_dir_all(root).unwrap();
+}
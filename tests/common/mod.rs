@@ -0,0 +1,82 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use cargo_backup::{
+    interrupt,
+    plan::Step,
+    runner::{RecordingRunner, RunError, Runner},
+    Package,
+};
+
+pub fn package(name: &str, version: &str) -> Package {
+    Package::new(name, semver::Version::parse(version).unwrap())
+}
+
+/// Interrupts the restore while the first step runs.
+#[derive(Default)]
+pub struct InterruptingRunner {
+    pub inner: RecordingRunner,
+}
+
+impl Runner for InterruptingRunner {
+    fn install(&mut self, step: &Step) -> Result<(), RunError> {
+        interrupt();
+        self.inner.install(step)
+    }
+
+    fn uninstall(&mut self, step: &Step) -> Result<(), RunError> {
+        self.inner.uninstall(step)
+    }
+}
+
+/// Creates an empty cargo home with trunk installed, a data directory
+/// and a fake rustup that logs its arguments and knows no toolchains.
+#[cfg(unix)]
+pub fn setup(name: &str) -> std::path::PathBuf {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let root = std::env::temp_dir().join(format!("cargo-backup-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    fs::create_dir_all(root.join("cargo")).unwrap();
+    fs::write(
+        root.join("cargo/.crates2.json"),
+        r#"{"installs":{"trunk 0.18.0 (registry+https://github.com/rust-lang/crates.io-index)":{"features":[],"all_features":false,"no_default_features":false}}}"#,
+    )
+    .unwrap();
+
+    fs::create_dir_all(root.join("bin")).unwrap();
+    let rustup = root.join("bin/rustup");
+    fs::write(
+        &rustup,
+        "#!/bin/sh\necho \"$@\" >> \"$(dirname \"$0\")/rustup.log\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&rustup, fs::Permissions::from_mode(0o755)).unwrap();
+
+    root
+}
+
+/// Returns the PATH with the fake rustup of [`setup`] first.
+pub fn path_with(root: &std::path::Path) -> String {
+    format!(
+        "{}:{}",
+        root.join("bin").display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
+pub fn rustup_log(root: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(root.join("bin/rustup.log")).ok()
+}
+
+/// A backup with a package that needs the nightly toolchain.
+pub fn backup() -> Vec<Package> {
+    vec![
+        Package {
+            toolchain: Some("nightly".to_string()),
+            ..package("bat", "0.24.0")
+        },
+        package("ripgrep", "14.0.0"),
+    ]
+}
@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use cargo_backup::{
+    history, install_packages, lock, resume_restore, runner::RecordingRunner, Confirmation,
+    RestoreError, RestoreOptions,
+};
+use common::{backup, path_with, rustup_log, setup, InterruptingRunner};
+use std::{env, fs};
+
+mod common;
+
+#[test]
+fn test_install_packages() {
+    let root = setup("install");
+    // The only test of this binary, because it changes the environment of the process
+    env::set_var("XDG_CONFIG_HOME", root.join("config"));
+    env::set_var("CARGO_HOME", root.join("cargo"));
+    env::set_var("CARGO_BACKUP_DATA_DIR", root.join("data"));
+    env::set_var("PATH", path_with(&root));
+
+    let options = RestoreOptions {
+        confirm: Confirmation::Never,
+        ..RestoreOptions::default()
+    };
+
+    // Another restore is running
+    let lock = lock::acquire(false).unwrap();
+    let mut runner = RecordingRunner::default();
+    let result = install_packages(&backup(), &options, &mut runner);
+    assert!(matches!(result, Err(RestoreError::Lock(_))));
+    assert!(runner.steps.is_empty());
+    assert_eq!(rustup_log(&root), None);
+    drop(lock);
+
+    // Interrupted after installing bat with the missing toolchain
+    let mut runner = InterruptingRunner::default();
+    let result = install_packages(&backup(), &options, &mut runner);
+    assert!(matches!(result, Err(RestoreError::Interrupted)));
+    assert_eq!(runner.inner.steps.len(), 1);
+    assert_eq!(
+        rustup_log(&root).unwrap(),
+        "toolchain list\ntoolchain install nightly --profile minimal\n"
+    );
+
+    let unfinished = history::unfinished().unwrap();
+    assert_eq!(unfinished.outcomes.len(), 1);
+    assert_eq!(unfinished.outcomes[0].package, "bat");
+    assert!(history::read().is_empty());
+
+    // Resumed with the steps that did not run yet
+    let mut runner = RecordingRunner::default();
+    let plan = resume_restore(&options, &mut runner).unwrap();
+    assert_eq!(
+        runner
+            .steps
+            .iter()
+            .map(|step| step.package.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["ripgrep", "trunk"]
+    );
+    assert_eq!(plan.steps().count(), 2);
+    assert_eq!(history::unfinished(), None);
+
+    let history = history::read();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].outcomes.len(), 3);
+    assert!(history[0].failures().next().is_none());
+
+    fs::remove_dir_all(root).unwrap();
+}
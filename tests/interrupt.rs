@@ -0,0 +1,32 @@
+use cargo_backup::{
+    apply_plan,
+    plan::{plan, PlanOptions},
+    platform::Host,
+    runner::RecordingRunner,
+    Confirmation, RestoreError,
+};
+use common::{package, InterruptingRunner};
+
+mod common;
+
+#[test]
+fn test_interrupt_stops_after_current_step() {
+    let plan = plan(
+        &[package("trunk", "1.0.0")],
+        &[package("bat", "1.0.0"), package("ripgrep", "1.0.0")],
+        &PlanOptions::default(),
+        &Host::current(),
+    );
+
+    let mut runner = InterruptingRunner::default();
+    let result = apply_plan(&plan, Confirmation::Never, &mut runner);
+
+    assert!(matches!(result, Err(RestoreError::Interrupted)));
+    assert_eq!(runner.inner.steps.len(), 1);
+    assert_eq!(runner.inner.steps[0].package.name, "bat");
+
+    // The next restore of the process is not interrupted anymore
+    let mut runner = RecordingRunner::default();
+    let outcomes = apply_plan(&plan, Confirmation::Never, &mut runner).unwrap();
+    assert_eq!(outcomes.len(), 3);
+}
@@ -0,0 +1,71 @@
+use cargo_backup::{
+    apply_plan,
+    plan::{plan, Action, PlanOptions},
+    platform::Host,
+    runner::RecordingRunner,
+    Confirmation,
+};
+
+use common::package;
+
+mod common;
+
+#[test]
+fn test_restore() {
+    let installed = vec![package("ripgrep", "13.0.0"), package("trunk", "0.18.0")];
+    let backup = vec![package("ripgrep", "14.0.0"), package("bat", "0.24.0")];
+
+    let plan = plan(
+        &installed,
+        &backup,
+        &PlanOptions::default(),
+        &Host::current(),
+    );
+    let mut runner = RecordingRunner::default();
+    apply_plan(&plan, Confirmation::Never, &mut runner).unwrap();
+
+    assert_eq!(
+        runner
+            .steps
+            .iter()
+            .map(|step| (step.action, step.command.join(" ")))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Action::Install,
+                "cargo install bat --version 0.24.0".to_string()
+            ),
+            (
+                Action::Update,
+                "cargo install ripgrep --version 14.0.0".to_string()
+            ),
+            (Action::Remove, "cargo uninstall trunk".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_restore_continues_after_failure() {
+    let backup = vec![package("cargo-edit", "0.12.0"), package("bat", "0.24.0")];
+
+    let plan = plan(&[], &backup, &PlanOptions::default(), &Host::current());
+    let mut runner = RecordingRunner::default().failing("cargo-edit");
+    let outcomes = apply_plan(&plan, Confirmation::Never, &mut runner).unwrap();
+
+    assert_eq!(runner.steps.len(), 2);
+    assert!(outcomes[0].error.is_some());
+    assert_eq!(outcomes[1].package, "bat");
+    assert_eq!(outcomes[1].error, None);
+}
+
+#[test]
+fn test_select_nothing_to_do() {
+    let backup = vec![package("bat", "0.24.0")];
+
+    let plan = plan(&backup, &backup, &PlanOptions::default(), &Host::current());
+    let mut runner = RecordingRunner::default();
+    let outcomes = apply_plan(&plan, Confirmation::Select, &mut runner).unwrap();
+
+    assert!(outcomes.is_empty());
+    assert!(runner.steps.is_empty());
+}